@@ -76,16 +76,41 @@ pub enum AstNode {
     },
     Sql {
         op: String,
+        distinct: bool,
+        withs: Vec<AstNode>,
         from: Box<AstNode>,
+        joins: Vec<AstNode>,
         filters: Vec<AstNode>,
         groups: Vec<AstNode>,
         ops: Vec<AstNode>,
+        reshapes: Vec<AstNode>,
+        post_filters: Vec<AstNode>,
         sorts: Vec<AstNode>,
         take: Box<AstNode>,
         source_id: usize,
         start: usize,
     },
     SqlBracket(Vec<AstNode>),
+    Explain(Box<AstNode>),
+    Join {
+        how: String,
+        right: Box<AstNode>,
+        keys: Vec<AstNode>,
+        start: usize,
+        source_id: usize,
+    },
+    Columns {
+        exp: Box<AstNode>,
+        exclude: bool,
+        by_type: bool,
+        start: usize,
+        source_id: usize,
+    },
+    Handle {
+        exp: Box<AstNode>,
+        start: usize,
+        source_id: usize,
+    },
     Skip,
 }
 
@@ -0,0 +1,47 @@
+use std::env;
+use std::path::Path;
+use std::process::ExitCode;
+use std::sync::Arc;
+
+use jasmine::db::storage::{LocalStorage, Storage};
+use jasmine::db::verify::{compact_db, verify_db};
+
+const USAGE: &str = "usage: jdb verify|compact <db>";
+
+// jdb verify <db>: prints issues of a database, fails if any
+// jdb compact <db>: merges chunks of partitions, prints merged partitions
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let [command, db_path] = &args[..] else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+    let db_path = Path::new(db_path);
+    let storage: Arc<dyn Storage> = Arc::new(LocalStorage);
+    let result = match command.as_str() {
+        "verify" => verify_db(&storage, db_path).map(|issues| {
+            for issue in issues.iter() {
+                println!("{}", issue);
+            }
+            issues.is_empty()
+        }),
+        "compact" => compact_db(&storage, db_path).map(|paths| {
+            for path in paths {
+                println!("{}", path.display());
+            }
+            true
+        }),
+        _ => {
+            eprintln!("{}", USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
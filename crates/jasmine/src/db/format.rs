@@ -0,0 +1,138 @@
+use std::fs::File;
+use std::io::{Cursor, Read};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use polars::frame::DataFrame;
+use polars::io::{SerReader, SerWriter};
+use polars::prelude::{
+    concat, IntoLazy, IpcCompression, IpcReader, IpcWriter, LazyFrame, ParquetReader,
+    ParquetWriter, ScanArgsIpc, ScanArgsParquet, UnionArgs,
+};
+
+use crate::db::storage::{write_atomic, Storage};
+use crate::errors::JResult;
+
+// arrow ipc files start with it, parquet files with 'PAR1'
+const IPC_MAGIC: &[u8] = b"ARROW1";
+
+// on-disk format of a table, recorded in its scheme, parquet if not recorded
+//   ipc: arrow ipc file, memory mapped when read
+//   ipc_lz4: lz4 compressed arrow ipc file
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum TableFormat {
+    Parquet,
+    Ipc,
+    IpcLz4,
+}
+
+impl TableFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "parquet" => Some(Self::Parquet),
+            "ipc" => Some(Self::Ipc),
+            "ipc_lz4" => Some(Self::IpcLz4),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Parquet => "parquet",
+            Self::Ipc => "ipc",
+            Self::IpcLz4 => "ipc_lz4",
+        }
+    }
+}
+
+// files are read by their content, a table may keep files written before its format changed
+fn is_ipc(path: &Path) -> JResult<bool> {
+    let mut magic = [0u8; 6];
+    let mut file = File::open(path)?;
+    Ok(file.read_exact(&mut magic).is_ok() && magic == IPC_MAGIC)
+}
+
+// local files of paths, none if any is not on a local filesystem
+fn local_paths(storage: &dyn Storage, paths: &[PathBuf]) -> Option<Vec<PathBuf>> {
+    paths.iter().map(|path| storage.local_path(path)).collect()
+}
+
+// files of other storages are read into memory
+pub fn read_file(storage: &dyn Storage, path: &Path) -> JResult<DataFrame> {
+    let Some(path) = storage.local_path(path) else {
+        let bytes = storage.read(path)?;
+        return if bytes.starts_with(IPC_MAGIC) {
+            Ok(IpcReader::new(Cursor::new(bytes)).finish()?)
+        } else {
+            Ok(ParquetReader::new(Cursor::new(bytes)).finish()?)
+        };
+    };
+    if is_ipc(&path)? {
+        Ok(IpcReader::new(File::open(&path)?)
+            .memory_mapped(Some(path))
+            .finish()?)
+    } else {
+        Ok(ParquetReader::new(File::open(&path)?).finish()?)
+    }
+}
+
+pub fn scan_file(storage: &dyn Storage, path: &Path) -> JResult<LazyFrame> {
+    let Some(path) = storage.local_path(path) else {
+        return Ok(read_file(storage, path)?.lazy());
+    };
+    if is_ipc(&path)? {
+        Ok(LazyFrame::scan_ipc(path, ScanArgsIpc::default())?)
+    } else {
+        Ok(LazyFrame::scan_parquet(path, ScanArgsParquet::default())?)
+    }
+}
+
+// files of the same schema, scanned together if they are local files of the same format
+pub fn scan_files(storage: &dyn Storage, paths: &[PathBuf]) -> JResult<LazyFrame> {
+    let Some(local) = local_paths(storage, paths) else {
+        return concat_files(storage, paths);
+    };
+    let ipc = local
+        .iter()
+        .map(|path| is_ipc(path))
+        .collect::<JResult<Vec<_>>>()?;
+    if ipc.iter().all(|ipc| !ipc) {
+        Ok(LazyFrame::scan_parquet_files(
+            Arc::from(local),
+            ScanArgsParquet::default(),
+        )?)
+    } else if ipc.iter().all(|ipc| *ipc) {
+        Ok(LazyFrame::scan_ipc_files(
+            Arc::from(local),
+            ScanArgsIpc::default(),
+        )?)
+    } else {
+        concat_files(storage, paths)
+    }
+}
+
+fn concat_files(storage: &dyn Storage, paths: &[PathBuf]) -> JResult<LazyFrame> {
+    let lfs = paths
+        .iter()
+        .map(|path| scan_file(storage, path))
+        .collect::<JResult<Vec<_>>>()?;
+    Ok(concat(lfs, UnionArgs::default())?)
+}
+
+// serialized in memory, then written atomically by the storage
+pub fn write_file_atomic(
+    storage: &dyn Storage,
+    df: &mut DataFrame,
+    path: &Path,
+    format: TableFormat,
+) -> JResult<()> {
+    let mut bytes = Vec::new();
+    match format {
+        TableFormat::Parquet => ParquetWriter::new(&mut bytes).finish(df).map(|_| ())?,
+        TableFormat::Ipc => IpcWriter::new(&mut bytes).finish(df)?,
+        TableFormat::IpcLz4 => IpcWriter::new(&mut bytes)
+            .with_compression(Some(IpcCompression::LZ4))
+            .finish(df)?,
+    }
+    write_atomic(storage, path, &bytes)
+}
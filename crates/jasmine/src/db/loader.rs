@@ -0,0 +1,107 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use indexmap::IndexMap;
+
+use crate::db::manifest::Manifest;
+use crate::db::parted::{Parted, PartitionUnit};
+use crate::db::scheme::PartitionScheme;
+use crate::db::storage::Storage;
+use crate::errors::{JError, JResult};
+use crate::j::J;
+
+// tables of a database directory, skips names starting with a digit or '.'
+pub fn load_db(storage: &Arc<dyn Storage>, db_path: &Path) -> JResult<IndexMap<String, J>> {
+    if !storage.is_dir(db_path) {
+        return Err(JError::Err(format!(
+            "not a database directory '{}'",
+            db_path.display()
+        )));
+    }
+    let names: Vec<String> = storage
+        .list(db_path)?
+        .into_iter()
+        .filter(|name| !name.starts_with(|c: char| c.is_ascii_digit() || c == '.'))
+        .collect();
+    let mut tables = IndexMap::new();
+    for name in names {
+        if let Some(parted) = load_table(storage, &db_path.join(&name))? {
+            tables.insert(name, J::Parted(parted));
+        }
+    }
+    Ok(tables)
+}
+
+// partition scheme is read from 'table/.scheme', or inferred from digits of partition names
+// for tables written before schemes were recorded, none if no partition
+pub fn load_table(storage: &Arc<dyn Storage>, table_path: &Path) -> JResult<Option<Parted>> {
+    if storage.is_file(table_path) {
+        return Ok(Some(Parted::new(
+            storage.clone(),
+            table_path,
+            PartitionScheme::new(PartitionUnit::Single),
+            vec![],
+        )));
+    }
+    if !storage.is_dir(table_path) {
+        return Ok(None);
+    }
+    // partitions of the latest version, chunks not published yet are not visible
+    let partitions = Manifest::read(storage.as_ref(), table_path, None)?.partitions();
+    if let Some(scheme) = PartitionScheme::read(storage.as_ref(), table_path)? {
+        return Ok(Some(Parted::new(
+            storage.clone(),
+            table_path,
+            scheme,
+            partitions,
+        )));
+    }
+    let mut unit: Option<PartitionUnit> = None;
+    for partition in partitions.iter() {
+        let partition_unit =
+            PartitionUnit::from_digits(partition.to_string().len()).ok_or(JError::Err(format!(
+                "unknown partition '{}' in '{}'",
+                partition,
+                table_path.display()
+            )))?;
+        match unit {
+            Some(unit) if unit != partition_unit => {
+                return Err(JError::Err(format!(
+                    "mixed partition units in '{}'",
+                    table_path.display()
+                )))
+            }
+            _ => unit = Some(partition_unit),
+        }
+    }
+    Ok(unit.map(|unit| {
+        Parted::new(
+            storage.clone(),
+            table_path,
+            PartitionScheme::new(unit),
+            partitions,
+        )
+    }))
+}
+
+// a partitioned table pinned to a kept version, its chunk files are kept while it is alive
+pub fn load_snapshot(
+    storage: &Arc<dyn Storage>,
+    table_path: &Path,
+    version: u64,
+) -> JResult<Parted> {
+    let parted = match load_table(storage, table_path)? {
+        Some(parted) if parted.scheme.unit != PartitionUnit::Single => parted,
+        _ => {
+            return Err(JError::Err(format!(
+                "not a partitioned table '{}'",
+                table_path.display()
+            )))
+        }
+    };
+    Parted {
+        version: Some(version),
+        ..parted
+    }
+    .pin()
+}
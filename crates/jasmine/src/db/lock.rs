@@ -0,0 +1,62 @@
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::db::storage::Storage;
+use crate::errors::{JError, JResult};
+
+// held by writers of a table from reading its latest version to retiring older ones,
+// 'table/.lock'
+pub const LOCK_FILE: &str = ".lock";
+
+// held by writers appending symbols and by symbol compaction, 'db/.sym.lock'
+pub const SYM_LOCK_FILE: &str = ".sym.lock";
+
+const LOCK_TIMEOUT: Duration = Duration::from_secs(30);
+const LOCK_RETRY: Duration = Duration::from_millis(10);
+
+// a lock file created only if it does not exist, removed when dropped, locks of a database
+// are taken in the order of tables by path then symbols
+pub struct FileLock<'a> {
+    storage: &'a dyn Storage,
+    path: PathBuf,
+}
+
+impl<'a> FileLock<'a> {
+    // none if held by another writer
+    pub fn try_acquire(storage: &'a dyn Storage, path: &Path) -> JResult<Option<Self>> {
+        let pid = std::process::id().to_string();
+        if storage.create_new(path, pid.as_bytes())? {
+            Ok(Some(Self {
+                storage,
+                path: path.to_path_buf(),
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+
+    // waits for other writers, a lock left by a crashed writer fails after the timeout
+    pub fn acquire(storage: &'a dyn Storage, path: &Path) -> JResult<Self> {
+        let start = Instant::now();
+        loop {
+            if let Some(lock) = Self::try_acquire(storage, path)? {
+                return Ok(lock);
+            }
+            if start.elapsed() > LOCK_TIMEOUT {
+                return Err(JError::Err(format!(
+                    "'{}' is held over {}s, remove it if no writer is running",
+                    path.display(),
+                    LOCK_TIMEOUT.as_secs()
+                )));
+            }
+            thread::sleep(LOCK_RETRY);
+        }
+    }
+}
+
+impl Drop for FileLock<'_> {
+    fn drop(&mut self) {
+        let _ = self.storage.delete(&self.path);
+    }
+}
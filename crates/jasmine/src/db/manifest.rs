@@ -0,0 +1,319 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::db::meta::update_meta;
+use crate::db::storage::{write_atomic, Storage};
+use crate::db::sym::SYM_FILE;
+use crate::db::writer::{chunk_name, parse_chunk, TMP_SUFFIX};
+use crate::errors::{JError, JResult};
+
+// versions of a partitioned table, 'table/.manifest/<nnnnnnnn>', chunk files of a version in
+// scan order, one per line, and a line of the symbol dictionary its codes refer to, '.sym' if
+// none, chunk files are never replaced, files of the latest versions and of versions pinned
+// by readers are kept
+pub const MANIFEST_DIR: &str = ".manifest";
+pub const KEPT_VERSIONS: u64 = 8;
+
+// readers pin a version by 'table/.manifest/<nnnnnnnn>_<pid>_<n>.pin', its creation time in
+// milliseconds then the manifest of the version, pins older than the timeout are removed as
+// left by crashed readers
+pub const PIN_SUFFIX: &str = ".pin";
+pub const PIN_TIMEOUT: Duration = Duration::from_secs(24 * 60 * 60);
+
+static PIN_SEQ: AtomicU64 = AtomicU64::new(0);
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct Manifest {
+    pub version: u64,
+    pub chunks: Vec<(i64, usize)>,
+    pub sym: String,
+}
+
+fn version_name(version: u64) -> String {
+    format!("{:08}", version)
+}
+
+// kept versions, sorted
+pub fn list_versions(storage: &dyn Storage, table_path: &Path) -> JResult<Vec<u64>> {
+    let dir = table_path.join(MANIFEST_DIR);
+    if !storage.is_dir(&dir) {
+        return Ok(Vec::new());
+    }
+    let mut versions: Vec<u64> = storage
+        .list(&dir)?
+        .iter()
+        .filter(|name| name.chars().all(|c| c.is_ascii_digit()))
+        .filter_map(|name| name.parse().ok())
+        .collect();
+    versions.sort();
+    Ok(versions)
+}
+
+impl Manifest {
+    // the latest version if none, chunk files of the table directory as version 0 for tables
+    // written before versions were recorded
+    pub fn read(storage: &dyn Storage, table_path: &Path, version: Option<u64>) -> JResult<Self> {
+        let versions = list_versions(storage, table_path)?;
+        let version = match (version, versions.last()) {
+            (None, None) | (Some(0), None) => return Self::list(storage, table_path),
+            (None, Some(latest)) => *latest,
+            (Some(version), _) if versions.contains(&version) => version,
+            (Some(version), _) => {
+                return Err(JError::Err(format!(
+                    "version {} of '{}' is not kept, kept versions {:?}",
+                    version,
+                    table_path.display(),
+                    versions
+                )))
+            }
+        };
+        let path = table_path.join(MANIFEST_DIR).join(version_name(version));
+        let content = String::from_utf8(storage.read(&path)?)
+            .map_err(|_| JError::Err(format!("invalid manifest '{}'", path.display())))?;
+        Self::parse(&path, version, content.lines())
+    }
+
+    fn parse<'a>(path: &Path, version: u64, lines: impl Iterator<Item = &'a str>) -> JResult<Self> {
+        let mut manifest = Self {
+            version,
+            chunks: Vec::new(),
+            sym: SYM_FILE.to_owned(),
+        };
+        for line in lines.filter(|line| !line.is_empty()) {
+            if line.starts_with('.') {
+                manifest.sym = line.to_owned();
+            } else {
+                manifest
+                    .chunks
+                    .push(parse_chunk(line).ok_or(JError::Err(format!(
+                        "invalid manifest '{}'",
+                        path.display()
+                    )))?);
+            }
+        }
+        Ok(manifest)
+    }
+
+    fn content(&self) -> String {
+        self.sym.clone()
+            + "\n"
+            + &self
+                .chunks
+                .iter()
+                .map(|(p, c)| chunk_name(*p, *c) + "\n")
+                .collect::<String>()
+    }
+
+    fn list(storage: &dyn Storage, table_path: &Path) -> JResult<Self> {
+        let mut chunks = Vec::new();
+        if storage.is_dir(table_path) {
+            chunks.extend(
+                storage
+                    .list(table_path)?
+                    .iter()
+                    .filter_map(|n| parse_chunk(n)),
+            );
+        }
+        chunks.sort();
+        Ok(Self {
+            version: 0,
+            chunks,
+            sym: SYM_FILE.to_owned(),
+        })
+    }
+
+    pub fn partitions(&self) -> Vec<i64> {
+        let mut partitions: Vec<i64> = self.chunks.iter().map(|(p, _)| *p).collect();
+        partitions.sort();
+        partitions.dedup();
+        partitions
+    }
+
+    // chunks of a partition in scan order
+    pub fn chunks_of(&self, partition: i64) -> Vec<usize> {
+        self.chunks
+            .iter()
+            .filter(|(p, _)| *p == partition)
+            .map(|(_, c)| *c)
+            .collect()
+    }
+
+    // added chunks are scanned after the others of the partition
+    pub fn replace(&mut self, partition: i64, removed: &[usize], added: &[usize]) {
+        self.chunks
+            .retain(|(p, c)| *p != partition || !removed.contains(c));
+        self.chunks.extend(added.iter().map(|c| (partition, *c)));
+    }
+
+    // writes the next version atomically, then removes versions no longer kept and chunk files
+    // none of the kept versions refers to, callers hold the table lock from reading the latest
+    // version
+    pub fn publish(&mut self, storage: &dyn Storage, table_path: &Path) -> JResult<()> {
+        self.version = list_versions(storage, table_path)?
+            .last()
+            .copied()
+            .unwrap_or(0)
+            + 1;
+        write_atomic(
+            storage,
+            &table_path
+                .join(MANIFEST_DIR)
+                .join(version_name(self.version)),
+            self.content().as_bytes(),
+        )?;
+        retire(storage, table_path, KEPT_VERSIONS)
+    }
+}
+
+fn retire(storage: &dyn Storage, table_path: &Path, kept_versions: u64) -> JResult<()> {
+    let versions = list_versions(storage, table_path)?;
+    let Some(latest) = versions.last().copied() else {
+        return Ok(());
+    };
+    let dir = table_path.join(MANIFEST_DIR);
+    let mut kept = HashSet::new();
+    for version in versions {
+        if version + kept_versions <= latest {
+            storage.delete(&dir.join(version_name(version)))?;
+        } else {
+            kept.extend(Manifest::read(storage, table_path, Some(version))?.chunks);
+        }
+    }
+    // versions are removed before reading pins, a reader finding its version after pinning it
+    // is always seen here
+    for pin in read_pins(storage, table_path)? {
+        kept.extend(pin.chunks);
+    }
+    let mut retired = Vec::new();
+    for name in storage.list(table_path)? {
+        if let Some(chunk) = parse_chunk(&name).filter(|chunk| !kept.contains(chunk)) {
+            storage.delete(&table_path.join(name))?;
+            retired.push(chunk);
+        }
+    }
+    if !retired.is_empty() {
+        update_meta(storage, table_path, &retired, None)?;
+    }
+    remove_manifest_tmp(storage, table_path)?;
+    Ok(())
+}
+
+// temp files of versions left by writers failed before publishing them, callers hold the
+// table lock, returns the number of removed files
+pub fn remove_manifest_tmp(storage: &dyn Storage, table_path: &Path) -> JResult<usize> {
+    let dir = table_path.join(MANIFEST_DIR);
+    if !storage.is_dir(&dir) {
+        return Ok(0);
+    }
+    let mut removed = 0;
+    for name in storage.list(&dir)? {
+        if is_manifest_tmp(&name) {
+            storage.delete(&dir.join(name))?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+// '.<nnnnnnnn>.tmp', temp files of pins are left to their readers
+pub fn is_manifest_tmp(name: &str) -> bool {
+    name.strip_prefix('.')
+        .and_then(|name| name.strip_suffix(TMP_SUFFIX))
+        .is_some_and(|name| name.len() == 8 && name.chars().all(|c| c.is_ascii_digit()))
+}
+
+fn now_ms() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis()
+}
+
+// manifests of versions pinned by readers, pins older than the timeout are removed
+pub fn read_pins(storage: &dyn Storage, table_path: &Path) -> JResult<Vec<Manifest>> {
+    let dir = table_path.join(MANIFEST_DIR);
+    if !storage.is_dir(&dir) {
+        return Ok(Vec::new());
+    }
+    let mut pins = Vec::new();
+    for name in storage.list(&dir)? {
+        let Some(version) = name
+            .strip_suffix(PIN_SUFFIX)
+            .and_then(|name| name.split('_').next())
+            .and_then(|version| version.parse().ok())
+        else {
+            continue;
+        };
+        let path = dir.join(&name);
+        // removed by its reader since listed
+        let Ok(bytes) = storage.read(&path) else {
+            continue;
+        };
+        let content = String::from_utf8(bytes)
+            .map_err(|_| JError::Err(format!("invalid pin '{}'", path.display())))?;
+        let mut lines = content.lines();
+        let created: u128 = lines
+            .next()
+            .and_then(|line| line.parse().ok())
+            .ok_or(JError::Err(format!("invalid pin '{}'", path.display())))?;
+        if created + PIN_TIMEOUT.as_millis() < now_ms() {
+            let _ = storage.delete(&path);
+            continue;
+        }
+        pins.push(Manifest::parse(&path, version, lines)?);
+    }
+    Ok(pins)
+}
+
+// a version pinned by a reader, its chunk files are kept until the pin is dropped
+pub struct Pin {
+    storage: Arc<dyn Storage>,
+    path: PathBuf,
+    pub manifest: Manifest,
+}
+
+impl Pin {
+    // pins a kept version, the latest if none
+    pub fn new(
+        storage: &Arc<dyn Storage>,
+        table_path: &Path,
+        version: Option<u64>,
+    ) -> JResult<Self> {
+        loop {
+            let manifest = Manifest::read(storage.as_ref(), table_path, version)?;
+            let name = format!(
+                "{}_{}_{}{}",
+                version_name(manifest.version),
+                process::id(),
+                PIN_SEQ.fetch_add(1, Ordering::Relaxed),
+                PIN_SUFFIX
+            );
+            let path = table_path.join(MANIFEST_DIR).join(name);
+            let content = format!("{}\n{}", now_ms(), manifest.content());
+            write_atomic(storage.as_ref(), &path, content.as_bytes())?;
+            let pin = Self {
+                storage: storage.clone(),
+                path,
+                manifest,
+            };
+            // retired while pinning, read the version again
+            let versions = list_versions(storage.as_ref(), table_path)?;
+            if match pin.manifest.version {
+                0 => versions.is_empty(),
+                version => versions.contains(&version),
+            } {
+                return Ok(pin);
+            }
+        }
+    }
+}
+
+impl Drop for Pin {
+    fn drop(&mut self) {
+        let _ = self.storage.delete(&self.path);
+    }
+}
@@ -0,0 +1,167 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use polars::frame::DataFrame;
+use polars::prelude::{col, BooleanChunked, Column, DataType, IntoLazy};
+
+use crate::db::format::{read_file, write_file_atomic, TableFormat};
+use crate::db::schema::{align, unify_schemas, widen_dtype};
+use crate::db::storage::Storage;
+use crate::db::writer::sort_df;
+use crate::errors::JResult;
+
+// chunk metadata of a partitioned table, 'table/.meta', one row per chunk
+//   partition, chunk, rows, sort: comma separated sort series, schema: 'name: type, ...'
+//   '<series>.min' and '<series>.max' of series with ordered types
+pub const META_FILE: &str = ".meta";
+
+fn has_stats(dtype: &DataType) -> bool {
+    dtype.is_numeric()
+        || dtype.is_temporal()
+        || matches!(dtype, DataType::String | DataType::Boolean)
+}
+
+pub fn chunk_meta(
+    partition: i64,
+    chunk: usize,
+    df: &DataFrame,
+    sort_series: &[String],
+) -> JResult<DataFrame> {
+    let schema = df
+        .schema()
+        .iter()
+        .map(|(name, dtype)| format!("{}: {}", name, dtype))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let mut meta = DataFrame::new(vec![
+        Column::new("partition".into(), [partition]),
+        Column::new("chunk".into(), [chunk as i64]),
+        Column::new("rows".into(), [df.height() as i64]),
+        Column::new("sort".into(), [sort_series.join(",")]),
+        Column::new("schema".into(), [schema]),
+    ])?;
+    let mut exprs = Vec::new();
+    for column in df.get_columns() {
+        if has_stats(column.dtype()) {
+            let name = column.name().as_str();
+            exprs.push(col(name).min().alias(format!("{}.min", name)));
+            exprs.push(col(name).max().alias(format!("{}.max", name)));
+        }
+    }
+    if !exprs.is_empty() {
+        let stats = df.clone().lazy().select(exprs).collect()?;
+        meta.hstack_mut(stats.get_columns())?;
+    }
+    Ok(meta)
+}
+
+pub fn read_meta(storage: &dyn Storage, table_path: &Path) -> JResult<Option<DataFrame>> {
+    let path = table_path.join(META_FILE);
+    if storage.is_file(&path) {
+        Ok(Some(read_file(storage, &path)?))
+    } else {
+        Ok(None)
+    }
+}
+
+// replaces rows of removed chunks with the added ones, written atomically, rows of chunks
+// replaced by a version are kept for pinned readers until their files are retired
+pub fn update_meta(
+    storage: &dyn Storage,
+    table_path: &Path,
+    removed: &[(i64, usize)],
+    added: Option<DataFrame>,
+) -> JResult<()> {
+    let mut frames = Vec::new();
+    if let Some(meta) = read_meta(storage, table_path)? {
+        let mask: BooleanChunked = meta
+            .column("partition")?
+            .i64()?
+            .into_iter()
+            .zip(meta.column("chunk")?.i64()?)
+            .map(|(p, c)| match (p, c) {
+                (Some(p), Some(c)) => !removed.contains(&(p, c as usize)),
+                _ => true,
+            })
+            .collect();
+        frames.push(meta.filter(&mask)?);
+    }
+    frames.extend(added);
+    if frames.is_empty() {
+        return Ok(());
+    }
+    // stats of series changing to an incompatible type are dropped as unknown
+    if let [meta, added] = &mut frames[..] {
+        let conflicts: Vec<String> = added
+            .schema()
+            .iter()
+            .filter(|(name, dtype)| {
+                meta.schema()
+                    .get(name)
+                    .is_some_and(|d| widen_dtype(d, dtype).is_none())
+            })
+            .map(|(name, _)| name.to_string())
+            .collect();
+        for name in conflicts {
+            meta.drop_in_place(&name)?;
+            added.drop_in_place(&name)?;
+        }
+    }
+    let schemas = frames
+        .iter()
+        .map(|df| (PathBuf::from(META_FILE), Arc::new(df.schema())))
+        .collect::<Vec<_>>();
+    let unified = unify_schemas(&schemas)?;
+    let mut meta: Option<DataFrame> = None;
+    for (df, (_, schema)) in frames.into_iter().zip(schemas.iter()) {
+        let df = align(df.lazy(), schema, &unified).collect()?;
+        match meta.as_mut() {
+            Some(meta) => {
+                meta.vstack_mut(&df)?;
+            }
+            None => meta = Some(df),
+        }
+    }
+    let meta = meta.unwrap_or(DataFrame::empty_with_schema(&unified));
+    let mut meta = sort_df(&meta, &["partition".to_owned(), "chunk".to_owned()])?;
+    meta.rechunk_mut();
+    write_file_atomic(
+        storage,
+        &mut meta,
+        &table_path.join(META_FILE),
+        TableFormat::Parquet,
+    )
+}
+
+// sort series recorded by all chunks of a partition, none if they differ or are unknown
+pub fn recorded_sort(
+    meta: Option<&DataFrame>,
+    partition: i64,
+    chunks: &[usize],
+) -> JResult<Vec<String>> {
+    let Some(meta) = meta else {
+        return Ok(Vec::new());
+    };
+    let mut sorts = Vec::with_capacity(chunks.len());
+    for ((p, c), sort) in meta
+        .column("partition")?
+        .i64()?
+        .into_iter()
+        .zip(meta.column("chunk")?.i64()?)
+        .zip(meta.column("sort")?.str()?)
+    {
+        if p == Some(partition) && c.is_some_and(|c| chunks.contains(&(c as usize))) {
+            sorts.push(sort.unwrap_or_default());
+        }
+    }
+    match sorts.first() {
+        Some(sort)
+            if !sort.is_empty()
+                && sorts.len() == chunks.len()
+                && sorts.iter().all(|s| s == sort) =>
+        {
+            Ok(sort.split(',').map(|s| s.to_owned()).collect())
+        }
+        _ => Ok(Vec::new()),
+    }
+}
@@ -0,0 +1,12 @@
+pub mod format;
+pub mod loader;
+pub mod lock;
+pub mod manifest;
+pub mod meta;
+pub mod parted;
+pub mod schema;
+pub mod scheme;
+pub mod storage;
+pub mod sym;
+pub mod verify;
+pub mod writer;
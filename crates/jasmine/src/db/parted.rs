@@ -0,0 +1,207 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use polars::prelude::LazyFrame;
+
+use crate::db::format::scan_file;
+use crate::db::manifest::{Manifest, Pin};
+use crate::db::schema::scan_unified;
+use crate::db::scheme::PartitionScheme;
+use crate::db::storage::Storage;
+use crate::db::sym::{decode, SymDict};
+use crate::db::writer::chunk_name;
+use crate::errors::{JError, JResult};
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum PartitionUnit {
+    Single,
+    Year,
+    Month,
+    Date,
+    Int,
+    Hash,
+}
+
+impl PartitionUnit {
+    // digits of partition names without a recorded scheme, 'yyyy', 'yyyymm' or 'yyyymmdd'
+    pub fn from_digits(digits: usize) -> Option<Self> {
+        match digits {
+            4 => Some(PartitionUnit::Year),
+            6 => Some(PartitionUnit::Month),
+            8 => Some(PartitionUnit::Date),
+            _ => None,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "single" => Some(PartitionUnit::Single),
+            "year" => Some(PartitionUnit::Year),
+            "month" => Some(PartitionUnit::Month),
+            "date" => Some(PartitionUnit::Date),
+            "int" => Some(PartitionUnit::Int),
+            "hash" => Some(PartitionUnit::Hash),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            PartitionUnit::Single => "single",
+            PartitionUnit::Year => "year",
+            PartitionUnit::Month => "month",
+            PartitionUnit::Date => "date",
+            PartitionUnit::Int => "int",
+            PartitionUnit::Hash => "hash",
+        }
+    }
+}
+
+// a table on disk, a single file or partitions as 'table/<partition>_<nnnn>'
+//   storage: files of the database the table was loaded from
+//   version: pinned version of partitions, none to scan the latest version
+//   pin: keeps chunk files of the pinned version while any copy of the table is alive
+#[derive(Clone)]
+pub struct Parted {
+    pub storage: Arc<dyn Storage>,
+    pub path: PathBuf,
+    pub scheme: PartitionScheme,
+    pub partitions: Vec<i64>,
+    pub version: Option<u64>,
+    pub pin: Option<Arc<Pin>>,
+}
+
+impl Parted {
+    pub fn new(
+        storage: Arc<dyn Storage>,
+        path: &Path,
+        scheme: PartitionScheme,
+        partitions: Vec<i64>,
+    ) -> Self {
+        Self {
+            storage,
+            path: path.to_path_buf(),
+            scheme,
+            partitions,
+            version: None,
+            pin: None,
+        }
+    }
+
+    // a copy reading one version, the pinned or the latest one, later writes are not visible
+    // and its chunk files are not retired
+    pub fn pin(&self) -> JResult<Self> {
+        if self.scheme.unit == PartitionUnit::Single || self.pin.is_some() {
+            return Ok(self.clone());
+        }
+        let pin = Pin::new(&self.storage, &self.path, self.version)?;
+        Ok(Self {
+            partitions: pin.manifest.partitions(),
+            version: Some(pin.manifest.version),
+            pin: Some(Arc::new(pin)),
+            ..self.clone()
+        })
+    }
+
+    // the pinned manifest, or the version read now
+    fn manifest(&self) -> JResult<Manifest> {
+        match &self.pin {
+            Some(pin) => Ok(pin.manifest.clone()),
+            None => Manifest::read(self.storage.as_ref(), &self.path, self.version),
+        }
+    }
+
+    // series name referring to partitions
+    pub fn get_unit(&self) -> &str {
+        &self.scheme.series
+    }
+
+    // chunks of existing partitions in a version, in scan order
+    pub fn chunks_of(&self, manifest: &Manifest, partitions: &[i64]) -> Vec<(i64, usize)> {
+        let mut chunks = Vec::new();
+        for partition in partitions {
+            if self.partitions.binary_search(partition).is_ok() {
+                for chunk in manifest.chunks_of(*partition) {
+                    chunks.push((*partition, chunk));
+                }
+            }
+        }
+        chunks
+    }
+
+    // scans all partitions if none, chunk files are of one version
+    pub fn scan(&self, partitions: Option<&[i64]>) -> JResult<LazyFrame> {
+        if self.scheme.unit == PartitionUnit::Single {
+            return scan_file(self.storage.as_ref(), &self.path);
+        }
+        let manifest = self.manifest()?;
+        let chunks = self.chunks_of(&manifest, partitions.unwrap_or(&self.partitions));
+        self.scan_version(&manifest, &chunks)
+    }
+
+    // scans chunks of the pinned version in order, e.g. chunks left by their metadata
+    pub fn scan_chunks(&self, chunks: &[(i64, usize)]) -> JResult<LazyFrame> {
+        let manifest = self.manifest()?;
+        self.scan_version(&manifest, chunks)
+    }
+
+    // an empty frame with the latest schema if no chunk, chunks of different schemas are
+    // scanned as the unified one, enumerated series are decoded as categorical series
+    fn scan_version(&self, manifest: &Manifest, chunks: &[(i64, usize)]) -> JResult<LazyFrame> {
+        let paths = |chunks: &[(i64, usize)]| -> Vec<PathBuf> {
+            chunks
+                .iter()
+                .map(|(partition, chunk)| self.path.join(chunk_name(*partition, *chunk)))
+                .collect()
+        };
+        if chunks.is_empty() {
+            let latest = self.partitions.last().ok_or(JError::Err(format!(
+                "no partition in '{}'",
+                self.path.display()
+            )))?;
+            let paths = paths(&self.chunks_of(manifest, &[*latest]));
+            self.decode(
+                scan_unified(self.storage.as_ref(), &paths)?.limit(0),
+                &manifest.sym,
+            )
+        } else {
+            self.decode(
+                scan_unified(self.storage.as_ref(), &paths(chunks))?,
+                &manifest.sym,
+            )
+        }
+    }
+
+    // maps codes of enumerated series back to categorical series of the dictionary of a version
+    fn decode(&self, lf: LazyFrame, sym: &str) -> JResult<LazyFrame> {
+        if self.scheme.enums.is_empty() {
+            return Ok(lf);
+        }
+        let db_path = self.path.parent().unwrap_or(Path::new("."));
+        let syms = SymDict::load(self.storage.as_ref(), db_path, sym)?.to_series()?;
+        Ok(decode(lf, &syms, &self.scheme.enums))
+    }
+}
+
+// tables of the same storage are equal by their files
+impl PartialEq for Parted {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.storage, &other.storage)
+            && self.path == other.path
+            && self.scheme == other.scheme
+            && self.partitions == other.partitions
+            && self.version == other.version
+    }
+}
+
+impl fmt::Debug for Parted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Parted")
+            .field("path", &self.path)
+            .field("scheme", &self.scheme)
+            .field("partitions", &self.partitions)
+            .field("version", &self.version)
+            .finish()
+    }
+}
@@ -0,0 +1,142 @@
+use std::path::{Path, PathBuf};
+
+use polars::prelude::{
+    col, concat, lit, DataType, Expr, LazyFrame, Schema, SchemaRef, UnionArgs, NULL,
+};
+
+use crate::db::format::{scan_file, scan_files};
+use crate::db::storage::Storage;
+use crate::errors::{JError, JResult};
+
+pub fn read_schema(storage: &dyn Storage, path: &Path) -> JResult<SchemaRef> {
+    Ok(scan_file(storage, path)?.collect_schema()?)
+}
+
+fn int_bits(dtype: &DataType) -> Option<(bool, u8)> {
+    match dtype {
+        DataType::Int8 => Some((true, 8)),
+        DataType::Int16 => Some((true, 16)),
+        DataType::Int32 => Some((true, 32)),
+        DataType::Int64 => Some((true, 64)),
+        DataType::UInt8 => Some((false, 8)),
+        DataType::UInt16 => Some((false, 16)),
+        DataType::UInt32 => Some((false, 32)),
+        DataType::UInt64 => Some((false, 64)),
+        _ => None,
+    }
+}
+
+fn int_dtype(signed: bool, bits: u8) -> DataType {
+    match (signed, bits) {
+        (true, 8) => DataType::Int8,
+        (true, 16) => DataType::Int16,
+        (true, 32) => DataType::Int32,
+        (true, _) => DataType::Int64,
+        (false, 8) => DataType::UInt8,
+        (false, 16) => DataType::UInt16,
+        (false, 32) => DataType::UInt32,
+        (false, _) => DataType::UInt64,
+    }
+}
+
+// a data type holding both without loss, none if incompatible
+pub fn widen_dtype(a: &DataType, b: &DataType) -> Option<DataType> {
+    if a == b {
+        return Some(a.clone());
+    }
+    match (a, b) {
+        (DataType::Null, _) => return Some(b.clone()),
+        (_, DataType::Null) => return Some(a.clone()),
+        (DataType::String, DataType::Categorical(_, _))
+        | (DataType::Categorical(_, _), DataType::String) => return Some(DataType::String),
+        _ => (),
+    }
+    match (int_bits(a), int_bits(b)) {
+        (Some((signed_a, bits_a)), Some((signed_b, bits_b))) => {
+            if signed_a == signed_b {
+                Some(int_dtype(signed_a, bits_a.max(bits_b)))
+            } else {
+                // unsigned fits in a signed integer of double bits
+                let (signed_bits, unsigned_bits) = if signed_a {
+                    (bits_a, bits_b)
+                } else {
+                    (bits_b, bits_a)
+                };
+                if unsigned_bits < 64 {
+                    Some(int_dtype(true, signed_bits.max(unsigned_bits * 2)))
+                } else {
+                    None
+                }
+            }
+        }
+        _ if (a.is_integer() || a.is_float()) && (b.is_integer() || b.is_float()) => {
+            Some(DataType::Float64)
+        }
+        _ => None,
+    }
+}
+
+// series in order of first appearance, incompatible partitions are all reported
+pub fn unify_schemas(schemas: &[(PathBuf, SchemaRef)]) -> JResult<Schema> {
+    let mut unified = Schema::default();
+    let mut incompatible = Vec::new();
+    for (path, schema) in schemas {
+        for (name, dtype) in schema.iter() {
+            match unified.get(name) {
+                Some(unified_dtype) => match widen_dtype(unified_dtype, dtype) {
+                    Some(widened) => {
+                        unified.with_column(name.clone(), widened);
+                    }
+                    None => incompatible.push(format!(
+                        "'{}' has '{}' as {}, expected {}",
+                        path.file_name().unwrap_or_default().to_string_lossy(),
+                        name,
+                        dtype,
+                        unified_dtype
+                    )),
+                },
+                None => {
+                    unified.with_column(name.clone(), dtype.clone());
+                }
+            }
+        }
+    }
+    if incompatible.is_empty() {
+        Ok(unified)
+    } else {
+        Err(JError::Err(format!(
+            "incompatible partitions - {}",
+            incompatible.join(", ")
+        )))
+    }
+}
+
+// missing series are filled with typed nulls, others are casted to the unified types
+pub fn align(lf: LazyFrame, schema: &Schema, unified: &Schema) -> LazyFrame {
+    let exprs: Vec<Expr> = unified
+        .iter()
+        .map(|(name, dtype)| match schema.get(name) {
+            Some(d) if d == dtype => col(name.clone()),
+            Some(_) => col(name.clone()).cast(dtype.clone()),
+            None => lit(NULL).cast(dtype.clone()).alias(name.clone()),
+        })
+        .collect();
+    lf.select(exprs)
+}
+
+// scans files as one frame of the unified schema
+pub fn scan_unified(storage: &dyn Storage, paths: &[PathBuf]) -> JResult<LazyFrame> {
+    let schemas = paths
+        .iter()
+        .map(|path| Ok((path.clone(), read_schema(storage, path)?)))
+        .collect::<JResult<Vec<_>>>()?;
+    if schemas.windows(2).all(|w| w[0].1 == w[1].1) {
+        return scan_files(storage, paths);
+    }
+    let unified = unify_schemas(&schemas)?;
+    let lfs = schemas
+        .iter()
+        .map(|(path, schema)| Ok(align(scan_file(storage, path)?, schema, &unified)))
+        .collect::<JResult<Vec<_>>>()?;
+    Ok(concat(lfs, UnionArgs::default())?)
+}
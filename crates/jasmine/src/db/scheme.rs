@@ -0,0 +1,130 @@
+use std::path::Path;
+
+use crate::db::format::TableFormat;
+use crate::db::parted::PartitionUnit;
+use crate::db::storage::{write_atomic, Storage};
+use crate::errors::{JError, JResult};
+
+// table metadata of the partition scheme, 'table/.scheme'
+pub const SCHEME_FILE: &str = ".scheme";
+
+// series: name referring to partitions in filters, the hashed series for hash partitions
+// buckets: number of hash buckets, 0 for others
+// enums: categorical series stored as codes of the database symbols
+// format: on-disk format of partitions
+#[derive(PartialEq, Debug, Clone)]
+pub struct PartitionScheme {
+    pub unit: PartitionUnit,
+    pub series: String,
+    pub buckets: i64,
+    pub enums: Vec<String>,
+    pub format: TableFormat,
+}
+
+impl PartitionScheme {
+    pub fn new(unit: PartitionUnit) -> Self {
+        Self {
+            unit,
+            series: unit.name().to_owned(),
+            buckets: 0,
+            enums: Vec::new(),
+            format: TableFormat::Parquet,
+        }
+    }
+
+    pub fn hash(series: &str, buckets: i64) -> JResult<Self> {
+        if buckets <= 0 {
+            return Err(JError::Err(format!(
+                "requires positive buckets for hash partitions, got {}",
+                buckets
+            )));
+        }
+        Ok(Self {
+            unit: PartitionUnit::Hash,
+            series: series.to_owned(),
+            buckets,
+            enums: Vec::new(),
+            format: TableFormat::Parquet,
+        })
+    }
+
+    // none if not recorded
+    pub fn read(storage: &dyn Storage, table_path: &Path) -> JResult<Option<Self>> {
+        let path = table_path.join(SCHEME_FILE);
+        if !storage.is_file(&path) {
+            return Ok(None);
+        }
+        let err = || JError::Err(format!("invalid partition scheme '{}'", path.display()));
+        let content = String::from_utf8(storage.read(&path)?).map_err(|_| err())?;
+        let mut unit = None;
+        let mut series = None;
+        let mut buckets = 0;
+        let mut enums = Vec::new();
+        let mut format = TableFormat::Parquet;
+        for line in content.lines() {
+            match line.split_once(':') {
+                Some(("unit", v)) => {
+                    unit = Some(PartitionUnit::from_name(v.trim()).ok_or_else(err)?)
+                }
+                Some(("series", v)) => series = Some(v.trim().to_owned()),
+                Some(("buckets", v)) => buckets = v.trim().parse::<i64>().map_err(|_| err())?,
+                Some(("enums", v)) => {
+                    enums = v
+                        .split(',')
+                        .map(|s| s.trim().to_owned())
+                        .filter(|s| !s.is_empty())
+                        .collect()
+                }
+                Some(("format", v)) => format = TableFormat::from_name(v.trim()).ok_or_else(err)?,
+                _ => (),
+            }
+        }
+        let unit = unit.ok_or_else(err)?;
+        let scheme = match unit {
+            PartitionUnit::Hash => Self::hash(&series.ok_or_else(err)?, buckets)?,
+            _ => Self::new(unit),
+        };
+        Ok(Some(scheme.with_enums(enums).with_format(format)))
+    }
+
+    pub fn with_enums(mut self, enums: Vec<String>) -> Self {
+        self.enums = enums;
+        self
+    }
+
+    pub fn with_format(mut self, format: TableFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    // same partitions regardless of enumerated series
+    pub fn is_same_partition(&self, other: &Self) -> bool {
+        self.unit == other.unit && self.series == other.series && self.buckets == other.buckets
+    }
+
+    pub fn write(&self, storage: &dyn Storage, table_path: &Path) -> JResult<()> {
+        let content = format!(
+            "unit: {}\nseries: {}\nbuckets: {}\nenums: {}\nformat: {}\n",
+            self.unit.name(),
+            self.series,
+            self.buckets,
+            self.enums.join(","),
+            self.format.name()
+        );
+        write_atomic(storage, &table_path.join(SCHEME_FILE), content.as_bytes())
+    }
+
+    pub fn bucket(&self, value: &str) -> i64 {
+        (hash_str(value) % self.buckets as u64) as i64
+    }
+}
+
+// FNV-1a, stable across processes and languages
+pub fn hash_str(s: &str) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for b in s.as_bytes() {
+        hash ^= *b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
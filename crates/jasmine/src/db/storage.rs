@@ -0,0 +1,197 @@
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use crate::db::writer::tmp_path;
+use crate::errors::{JError, JResult};
+
+// files of databases, paths are the same as on a local filesystem, directories exist as long
+// as they have files, passed to every reader and writer of a database
+pub trait Storage: Send + Sync {
+    // names directly under a directory, sorted
+    fn list(&self, dir: &Path) -> JResult<Vec<String>>;
+
+    fn is_file(&self, path: &Path) -> bool;
+
+    fn is_dir(&self, path: &Path) -> bool;
+
+    fn read(&self, path: &Path) -> JResult<Vec<u8>>;
+
+    // creates missing parent directories, replaces an existing file
+    fn write(&self, path: &Path, bytes: &[u8]) -> JResult<()>;
+
+    // creates missing parent directories, false if the file exists, atomic across writers
+    fn create_new(&self, path: &Path, bytes: &[u8]) -> JResult<bool>;
+
+    // replaces an existing file, atomic for readers
+    fn rename(&self, from: &Path, to: &Path) -> JResult<()>;
+
+    fn delete(&self, path: &Path) -> JResult<()>;
+
+    // path to be scanned lazily or memory mapped, none if files are not on a local filesystem
+    fn local_path(&self, _path: &Path) -> Option<PathBuf> {
+        None
+    }
+}
+
+#[derive(Default)]
+pub struct LocalStorage;
+
+impl Storage for LocalStorage {
+    fn list(&self, dir: &Path) -> JResult<Vec<String>> {
+        let mut names = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            names.push(entry?.file_name().to_string_lossy().to_string());
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        path.is_file()
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        path.is_dir()
+    }
+
+    fn read(&self, path: &Path) -> JResult<Vec<u8>> {
+        Ok(fs::read(path)?)
+    }
+
+    fn write(&self, path: &Path, bytes: &[u8]) -> JResult<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        Ok(fs::write(path, bytes)?)
+    }
+
+    fn create_new(&self, path: &Path, bytes: &[u8]) -> JResult<bool> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        match OpenOptions::new().write(true).create_new(true).open(path) {
+            Ok(mut file) => {
+                file.write_all(bytes)?;
+                Ok(true)
+            }
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> JResult<()> {
+        Ok(fs::rename(from, to)?)
+    }
+
+    fn delete(&self, path: &Path) -> JResult<()> {
+        Ok(fs::remove_file(path)?)
+    }
+
+    fn local_path(&self, path: &Path) -> Option<PathBuf> {
+        Some(path.to_path_buf())
+    }
+}
+
+// files kept in memory, for tests
+#[derive(Default)]
+pub struct MemoryStorage {
+    files: RwLock<BTreeMap<PathBuf, Vec<u8>>>,
+}
+
+fn not_found(path: &Path) -> JError {
+    JError::IoErr(io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("'{}' not found", path.display()),
+    ))
+}
+
+impl Storage for MemoryStorage {
+    fn list(&self, dir: &Path) -> JResult<Vec<String>> {
+        let files = self.files.read().unwrap();
+        let mut names: Vec<String> = files
+            .keys()
+            .filter_map(|path| path.strip_prefix(dir).ok()?.components().next())
+            .map(|name| name.as_os_str().to_string_lossy().to_string())
+            .collect();
+        if names.is_empty() {
+            return Err(not_found(dir));
+        }
+        names.dedup();
+        Ok(names)
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        self.files.read().unwrap().contains_key(path)
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        self.files
+            .read()
+            .unwrap()
+            .keys()
+            .any(|file| file != path && file.starts_with(path))
+    }
+
+    fn read(&self, path: &Path) -> JResult<Vec<u8>> {
+        self.files
+            .read()
+            .unwrap()
+            .get(path)
+            .cloned()
+            .ok_or_else(|| not_found(path))
+    }
+
+    fn write(&self, path: &Path, bytes: &[u8]) -> JResult<()> {
+        if self.is_dir(path) {
+            return Err(JError::Err(format!("'{}' is a directory", path.display())));
+        }
+        self.files
+            .write()
+            .unwrap()
+            .insert(path.to_path_buf(), bytes.to_vec());
+        Ok(())
+    }
+
+    fn create_new(&self, path: &Path, bytes: &[u8]) -> JResult<bool> {
+        if self.is_dir(path) {
+            return Err(JError::Err(format!("'{}' is a directory", path.display())));
+        }
+        let mut files = self.files.write().unwrap();
+        if files.contains_key(path) {
+            return Ok(false);
+        }
+        files.insert(path.to_path_buf(), bytes.to_vec());
+        Ok(true)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> JResult<()> {
+        let mut files = self.files.write().unwrap();
+        let bytes = files.remove(from).ok_or_else(|| not_found(from))?;
+        files.insert(to.to_path_buf(), bytes);
+        Ok(())
+    }
+
+    fn delete(&self, path: &Path) -> JResult<()> {
+        self.files
+            .write()
+            .unwrap()
+            .remove(path)
+            .map(|_| ())
+            .ok_or_else(|| not_found(path))
+    }
+}
+
+// writes to a hidden temp file then renames it, readers never see a partially written file
+pub fn write_atomic(storage: &dyn Storage, path: &Path, bytes: &[u8]) -> JResult<()> {
+    let tmp = tmp_path(path);
+    let result = storage
+        .write(&tmp, bytes)
+        .and_then(|_| storage.rename(&tmp, path));
+    if result.is_err() {
+        let _ = storage.delete(&tmp);
+    }
+    result
+}
@@ -0,0 +1,357 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use polars::frame::DataFrame;
+use polars::prelude::{
+    col, lit, CategoricalOrdering, Column, DataType, IntoColumn, IntoSeries, LazyFrame, NamedFrom,
+    Series, UInt32Chunked,
+};
+
+use crate::db::format::{read_file, write_file_atomic, TableFormat};
+use crate::db::loader::load_db;
+use crate::db::lock::{FileLock, LOCK_FILE, SYM_LOCK_FILE};
+use crate::db::manifest::{list_versions, read_pins, Manifest};
+use crate::db::meta::{chunk_meta, read_meta, recorded_sort, update_meta};
+use crate::db::parted::PartitionUnit;
+use crate::db::storage::Storage;
+use crate::db::writer::{chunk_name, free_chunk, sort_df};
+use crate::errors::{JError, JResult};
+use crate::j::J;
+
+// symbols of categorical series of all partitioned tables in a database, 'db/.sym', symbol
+// compaction writes the next dictionary as 'db/.sym_<nnnnnnnn>', each version of a table refers
+// to the dictionary of its codes
+pub const SYM_FILE: &str = ".sym";
+
+// '.sym' -> 0, '.sym_<nnnnnnnn>' -> n
+fn parse_sym(name: &str) -> Option<u64> {
+    if name == SYM_FILE {
+        return Some(0);
+    }
+    let digits = name.strip_prefix(SYM_FILE)?.strip_prefix('_')?;
+    if digits.len() != 8 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+fn sym_name(generation: u64) -> String {
+    if generation == 0 {
+        SYM_FILE.to_owned()
+    } else {
+        format!("{}_{:08}", SYM_FILE, generation)
+    }
+}
+
+// generations of dictionaries in a database, sorted
+fn list_syms(storage: &dyn Storage, db_path: &Path) -> JResult<Vec<u64>> {
+    let mut generations: Vec<u64> = storage
+        .list(db_path)?
+        .iter()
+        .filter(|name| storage.is_file(&db_path.join(name)))
+        .filter_map(|name| parse_sym(name))
+        .collect();
+    generations.sort();
+    Ok(generations)
+}
+
+// a symbol is enumerated by its position, appended only except by compaction
+pub struct SymDict {
+    path: PathBuf,
+    syms: Vec<String>,
+    index: HashMap<String, u32>,
+}
+
+impl SymDict {
+    // empty if not exists
+    pub fn load(storage: &dyn Storage, db_path: &Path, name: &str) -> JResult<Self> {
+        let path = db_path.join(name);
+        let syms: Vec<String> = if storage.is_file(&path) {
+            read_file(storage, &path)?
+                .column("sym")?
+                .str()?
+                .into_iter()
+                .map(|s| s.unwrap_or_default().to_owned())
+                .collect()
+        } else {
+            Vec::new()
+        };
+        Ok(Self::new(path, syms))
+    }
+
+    // the dictionary new tables are enumerated by, the last written one
+    pub fn latest(storage: &dyn Storage, db_path: &Path) -> JResult<Self> {
+        let generation = list_syms(storage, db_path)?.last().copied().unwrap_or(0);
+        Self::load(storage, db_path, &sym_name(generation))
+    }
+
+    fn new(path: PathBuf, syms: Vec<String>) -> Self {
+        let index = syms
+            .iter()
+            .enumerate()
+            .map(|(i, s)| (s.clone(), i as u32))
+            .collect();
+        Self { path, syms, index }
+    }
+
+    // file name referred by versions of tables
+    pub fn name(&self) -> String {
+        self.path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string()
+    }
+
+    pub fn len(&self) -> usize {
+        self.syms.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.syms.is_empty()
+    }
+
+    // symbols in order of their codes
+    pub fn syms(&self) -> &[String] {
+        &self.syms
+    }
+
+    pub fn get(&self, code: u32) -> Option<&str> {
+        self.syms.get(code as usize).map(|s| s.as_str())
+    }
+
+    // codes of string values, new symbols are appended, nulls are kept
+    pub fn enumerate(&mut self, column: &Column) -> JResult<Column> {
+        let strs = column.cast(&DataType::String)?;
+        let codes: UInt32Chunked = strs
+            .str()?
+            .into_iter()
+            .map(|s| {
+                s.map(|s| match self.index.get(s) {
+                    Some(code) => *code,
+                    None => {
+                        let code = self.syms.len() as u32;
+                        self.syms.push(s.to_owned());
+                        self.index.insert(s.to_owned(), code);
+                        code
+                    }
+                })
+            })
+            .collect();
+        Ok(codes
+            .into_series()
+            .with_name(column.name().clone())
+            .into_column())
+    }
+
+    pub fn save(&self, storage: &dyn Storage) -> JResult<()> {
+        let mut df = DataFrame::new(vec![Series::new("sym".into(), &self.syms).into_column()])?;
+        write_file_atomic(storage, &mut df, &self.path, TableFormat::Parquet)
+    }
+
+    // symbols as one categorical series, shared by all decoded partitions
+    pub fn to_series(&self) -> JResult<Series> {
+        Ok(Series::new("sym".into(), &self.syms)
+            .cast(&DataType::Categorical(None, CategoricalOrdering::Physical))?)
+    }
+}
+
+// replaces series with codes of a dictionary, returns their names
+//   recorded: enumerated series of an existing table, none for a new table
+//   sym: dictionary of an existing table, set to the latest one for a new table
+// categorical series of a new table and string or categorical series recorded as enumerated
+// are enumerated, others keep their types as chunks written before enumeration
+pub fn enumerate_df(
+    storage: &dyn Storage,
+    db_path: &Path,
+    df: &mut DataFrame,
+    recorded: Option<&[String]>,
+    sym: &mut String,
+) -> JResult<Vec<String>> {
+    let names: Vec<String> = df
+        .get_columns()
+        .iter()
+        .filter(|c| match (c.dtype(), recorded) {
+            (DataType::Categorical(_, _), None) => true,
+            (DataType::Categorical(_, _) | DataType::String, Some(enums)) => {
+                enums.iter().any(|name| name == c.name().as_str())
+            }
+            _ => false,
+        })
+        .map(|c| c.name().to_string())
+        .collect();
+    if names.is_empty() {
+        return Ok(names);
+    }
+    let _lock = FileLock::acquire(storage, &db_path.join(SYM_LOCK_FILE))?;
+    let mut dict = match recorded {
+        Some(_) => SymDict::load(storage, db_path, sym)?,
+        None => SymDict::latest(storage, db_path)?,
+    };
+    *sym = dict.name();
+    let size = dict.len();
+    for name in names.iter() {
+        let codes = dict.enumerate(df.column(name)?)?;
+        df.with_column(codes)?;
+    }
+    // saved before partitions, readers never see unknown codes
+    if dict.len() > size {
+        dict.save(storage)?;
+    }
+    Ok(names)
+}
+
+// maps codes back to categorical series
+pub fn decode(lf: LazyFrame, syms: &Series, enums: &[String]) -> LazyFrame {
+    if enums.is_empty() {
+        return lf;
+    }
+    lf.with_columns(
+        enums
+            .iter()
+            .map(|name| {
+                lit(syms.clone())
+                    .gather(col(name.as_str()))
+                    .alias(name.as_str())
+            })
+            .collect::<Vec<_>>(),
+    )
+}
+
+// removes symbols not referred by the latest version of any table, used symbols are written
+// to the next dictionary and each table is published as a version of new chunk files with new
+// codes, older versions and dictionaries are kept for pinned readers until retired, returns the
+// number of removed symbols
+pub fn compact_syms(storage: &Arc<dyn Storage>, db_path: &Path) -> JResult<usize> {
+    // writers of a table being created hold its lock before enumerating series
+    let mut locks = Vec::new();
+    for name in storage.list(db_path)? {
+        let path = db_path.join(&name);
+        if !name.starts_with(|c: char| c.is_ascii_digit() || c == '.') && storage.is_dir(&path) {
+            locks.push(FileLock::acquire(storage.as_ref(), &path.join(LOCK_FILE))?);
+        }
+    }
+    let _sym_lock = FileLock::acquire(storage.as_ref(), &db_path.join(SYM_LOCK_FILE))?;
+    let mut tables = Vec::new();
+    for (_, j) in load_db(storage, db_path)? {
+        if let J::Parted(parted) = j {
+            if parted.scheme.unit != PartitionUnit::Single && !parted.scheme.enums.is_empty() {
+                let manifest = Manifest::read(storage.as_ref(), &parted.path, None)?;
+                tables.push((parted, manifest));
+            }
+        }
+    }
+
+    // codes used by latest versions of each dictionary, versions written after a failed
+    // compaction may refer to different dictionaries
+    let latest = SymDict::latest(storage.as_ref(), db_path)?;
+    let mut dicts: HashMap<String, SymDict> = HashMap::from([(latest.name(), latest)]);
+    let mut used: HashMap<String, HashSet<u32>> = HashMap::new();
+    for (parted, manifest) in tables.iter() {
+        if !dicts.contains_key(&manifest.sym) {
+            let dict = SymDict::load(storage.as_ref(), db_path, &manifest.sym)?;
+            dicts.insert(manifest.sym.clone(), dict);
+        }
+        let codes = used.entry(manifest.sym.clone()).or_default();
+        for (partition, chunk) in manifest.chunks.iter() {
+            let df = read_file(
+                storage.as_ref(),
+                &parted.path.join(chunk_name(*partition, *chunk)),
+            )?;
+            for name in parted.scheme.enums.iter() {
+                if let Ok(column) = df.column(name) {
+                    codes.extend(column.u32()?.into_iter().flatten());
+                }
+            }
+        }
+    }
+    let mut names: Vec<&String> = dicts.keys().collect();
+    names.sort_by_key(|name| parse_sym(name));
+    let mut syms = Vec::new();
+    let mut index: HashMap<String, u32> = HashMap::new();
+    for name in names {
+        let mut codes: Vec<u32> = used.get(name).into_iter().flatten().copied().collect();
+        codes.sort();
+        for code in codes {
+            let sym = dicts[name].get(code).ok_or(JError::Err(format!(
+                "unknown symbol code {} in '{}'",
+                code,
+                db_path.join(name).display()
+            )))?;
+            if !index.contains_key(sym) {
+                index.insert(sym.to_owned(), syms.len() as u32);
+                syms.push(sym.to_owned());
+            }
+        }
+    }
+    let known: HashSet<&String> = dicts.values().flat_map(|dict| dict.syms.iter()).collect();
+    let removed = known.len() - syms.len();
+    if removed == 0 && dicts.len() == 1 {
+        return Ok(0);
+    }
+
+    let generation = list_syms(storage.as_ref(), db_path)?
+        .last()
+        .map_or(1, |generation| generation + 1);
+    let dict = SymDict::new(db_path.join(sym_name(generation)), syms);
+    // saved before versions referring to it
+    dict.save(storage.as_ref())?;
+    let mut referred = HashSet::from([dict.name()]);
+    for (parted, mut manifest) in tables {
+        let old = &dicts[&manifest.sym];
+        let meta = read_meta(storage.as_ref(), &parted.path)?;
+        let mut chunks = Vec::with_capacity(manifest.chunks.len());
+        let mut metas = Vec::with_capacity(manifest.chunks.len());
+        for (partition, chunk) in manifest.chunks.iter() {
+            let mut df = read_file(
+                storage.as_ref(),
+                &parted.path.join(chunk_name(*partition, *chunk)),
+            )?;
+            for name in parted.scheme.enums.iter() {
+                if let Ok(column) = df.column(name) {
+                    let codes: UInt32Chunked = column
+                        .u32()?
+                        .into_iter()
+                        .map(|c| c.and_then(|c| old.get(c)).map(|s| index[s]))
+                        .collect();
+                    df.with_column(codes.into_series().with_name(name.as_str().into()))?;
+                }
+            }
+            // codes of different dictionaries may be reordered
+            let sort_series = recorded_sort(meta.as_ref(), *partition, &[*chunk])?;
+            let mut df = sort_df(&df, &sort_series)?;
+            let new = free_chunk(storage.as_ref(), &parted.path, *partition)?;
+            write_file_atomic(
+                storage.as_ref(),
+                &mut df,
+                &parted.path.join(chunk_name(*partition, new)),
+                parted.scheme.format,
+            )?;
+            metas.push((
+                (*partition, new),
+                chunk_meta(*partition, new, &df, &sort_series)?,
+            ));
+            chunks.push((*partition, new));
+        }
+        manifest.chunks = chunks;
+        manifest.sym = dict.name();
+        manifest.publish(storage.as_ref(), &parted.path)?;
+        for (chunk, added) in metas {
+            update_meta(storage.as_ref(), &parted.path, &[chunk], Some(added))?;
+        }
+        for version in list_versions(storage.as_ref(), &parted.path)? {
+            referred.insert(Manifest::read(storage.as_ref(), &parted.path, Some(version))?.sym);
+        }
+        for pin in read_pins(storage.as_ref(), &parted.path)? {
+            referred.insert(pin.sym);
+        }
+    }
+    for generation in list_syms(storage.as_ref(), db_path)? {
+        let name = sym_name(generation);
+        if !referred.contains(&name) {
+            storage.delete(&db_path.join(name))?;
+        }
+    }
+    Ok(removed)
+}
@@ -0,0 +1,330 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use polars::frame::DataFrame;
+use polars::prelude::DataType;
+
+use crate::db::format::read_file;
+use crate::db::loader::{load_db, load_table};
+use crate::db::lock::{FileLock, LOCK_FILE, SYM_LOCK_FILE};
+use crate::db::manifest::{
+    is_manifest_tmp, list_versions, read_pins, remove_manifest_tmp, Manifest, MANIFEST_DIR,
+};
+use crate::db::meta::{read_meta, recorded_sort, META_FILE};
+use crate::db::parted::PartitionUnit;
+use crate::db::schema::unify_schemas;
+use crate::db::storage::Storage;
+use crate::db::sym::SymDict;
+use crate::db::writer::{chunk_name, list_chunks, merge_chunks, parse_chunk, TMP_SUFFIX};
+use crate::errors::{JError, JResult};
+use crate::j::J;
+
+// a problem found in a database, table is empty for the database directory itself
+#[derive(Debug, Clone, PartialEq)]
+pub struct Issue {
+    pub table: String,
+    pub path: PathBuf,
+    pub message: String,
+}
+
+impl Issue {
+    fn new(table: &str, path: &Path, message: String) -> Self {
+        Self {
+            table: table.to_owned(),
+            path: path.to_path_buf(),
+            message,
+        }
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.message)
+    }
+}
+
+// temp files left by a writer that failed before renaming them
+fn is_tmp(name: &str) -> bool {
+    name.starts_with('.') && name.ends_with(TMP_SUFFIX)
+}
+
+// checks every table of a database, returns all issues found instead of stopping at the first
+//   files: temp files, unknown files, unreadable partitions, chunks missing from the latest
+//          version, chunks no kept version refers to
+//   schemas: incompatible partitions, enumerated series with unknown symbol codes
+//   metadata: chunks without metadata, metadata of missing chunks, mismatched row counts
+pub fn verify_db(storage: &Arc<dyn Storage>, db_path: &Path) -> JResult<Vec<Issue>> {
+    if !storage.is_dir(db_path) {
+        return Err(JError::Err(format!(
+            "not a database directory '{}'",
+            db_path.display()
+        )));
+    }
+    let mut issues = Vec::new();
+    for name in storage.list(db_path)? {
+        let path = db_path.join(&name);
+        if is_tmp(&name) {
+            issues.push(Issue::new("", &path, "orphan temp file".to_owned()));
+        } else if !name.starts_with(|c: char| c.is_ascii_digit() || c == '.') {
+            verify_table(storage, db_path, &name, &path, &mut issues)?;
+        }
+    }
+    Ok(issues)
+}
+
+fn verify_table(
+    storage: &Arc<dyn Storage>,
+    db_path: &Path,
+    table: &str,
+    table_path: &Path,
+    issues: &mut Vec<Issue>,
+) -> JResult<()> {
+    if storage.is_file(table_path) {
+        if let Err(e) = read_file(storage.as_ref(), table_path) {
+            issues.push(Issue::new(table, table_path, e.to_string()));
+        }
+        return Ok(());
+    }
+    let parted = match load_table(storage, table_path) {
+        Ok(Some(parted)) => parted,
+        Ok(None) => return Ok(()),
+        Err(e) => {
+            issues.push(Issue::new(table, table_path, e.to_string()));
+            return Ok(());
+        }
+    };
+    let meta = match read_meta(storage.as_ref(), table_path) {
+        Ok(meta) => meta,
+        Err(e) => {
+            issues.push(Issue::new(
+                table,
+                &table_path.join(META_FILE),
+                e.to_string(),
+            ));
+            None
+        }
+    };
+
+    let latest = match Manifest::read(storage.as_ref(), table_path, None) {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            issues.push(Issue::new(table, table_path, e.to_string()));
+            return Ok(());
+        }
+    };
+    let syms = if parted.scheme.enums.is_empty() {
+        None
+    } else {
+        match SymDict::load(storage.as_ref(), db_path, &latest.sym) {
+            Ok(dict) => Some(dict.len()),
+            Err(e) => {
+                issues.push(Issue::new(table, &db_path.join(&latest.sym), e.to_string()));
+                None
+            }
+        }
+    };
+    let mut kept: HashSet<(i64, usize)> = latest.chunks.iter().copied().collect();
+    for version in list_versions(storage.as_ref(), table_path)? {
+        match Manifest::read(storage.as_ref(), table_path, Some(version)) {
+            Ok(manifest) => kept.extend(manifest.chunks),
+            Err(e) => issues.push(Issue::new(table, table_path, e.to_string())),
+        }
+    }
+    match read_pins(storage.as_ref(), table_path) {
+        Ok(pins) => kept.extend(pins.into_iter().flat_map(|pin| pin.chunks)),
+        Err(e) => issues.push(Issue::new(table, table_path, e.to_string())),
+    }
+    let manifest_dir = table_path.join(MANIFEST_DIR);
+    if storage.is_dir(&manifest_dir) {
+        for name in storage.list(&manifest_dir)? {
+            if is_manifest_tmp(&name) {
+                let path = manifest_dir.join(name);
+                issues.push(Issue::new(table, &path, "orphan temp file".to_owned()));
+            }
+        }
+    }
+
+    let mut rows: HashMap<(i64, usize), usize> = HashMap::new();
+    let mut schemas = Vec::new();
+    for name in storage.list(table_path)? {
+        let path = table_path.join(&name);
+        if is_tmp(&name) {
+            issues.push(Issue::new(table, &path, "orphan temp file".to_owned()));
+            continue;
+        } else if name.starts_with('.') {
+            continue;
+        }
+        let Some(key) = parse_chunk(&name) else {
+            issues.push(Issue::new(table, &path, "unknown file".to_owned()));
+            continue;
+        };
+        if !kept.contains(&key) {
+            issues.push(Issue::new(table, &path, "unreferenced chunk".to_owned()));
+            continue;
+        } else if !latest.chunks.contains(&key) {
+            // kept for readers pinned to an older version
+            continue;
+        }
+        let df = match read_file(storage.as_ref(), &path) {
+            Ok(df) => df,
+            Err(e) => {
+                issues.push(Issue::new(table, &path, e.to_string()));
+                continue;
+            }
+        };
+        if let Some(syms) = syms {
+            verify_codes(table, &path, &df, &parted.scheme.enums, syms, issues)?;
+        }
+        rows.insert(key, df.height());
+        schemas.push((path, Arc::new(df.schema())));
+    }
+    for (partition, chunk) in latest.chunks.iter() {
+        let path = table_path.join(chunk_name(*partition, *chunk));
+        if !storage.is_file(&path) {
+            issues.push(Issue::new(table, &path, "missing chunk".to_owned()));
+        }
+    }
+    if let Err(e) = unify_schemas(&schemas) {
+        issues.push(Issue::new(table, table_path, e.to_string()));
+    }
+
+    // tables written before chunk metadata are not checked
+    let Some(meta) = meta else {
+        return Ok(());
+    };
+    let meta_path = table_path.join(META_FILE);
+    let mut recorded: HashMap<(i64, usize), Option<i64>> = HashMap::new();
+    for ((partition, chunk), count) in meta
+        .column("partition")?
+        .i64()?
+        .into_iter()
+        .zip(meta.column("chunk")?.i64()?)
+        .zip(meta.column("rows")?.i64()?)
+    {
+        if let (Some(partition), Some(chunk)) = (partition, chunk) {
+            let key = (partition, chunk as usize);
+            if !kept.contains(&key) {
+                issues.push(Issue::new(
+                    table,
+                    &meta_path,
+                    format!(
+                        "metadata of missing chunk '{}'",
+                        chunk_name(partition, chunk as usize)
+                    ),
+                ));
+            }
+            recorded.insert(key, count);
+        }
+    }
+    let mut keys: Vec<_> = rows.keys().copied().collect();
+    keys.sort();
+    for key in keys {
+        let path = table_path.join(chunk_name(key.0, key.1));
+        match recorded.get(&key) {
+            None => issues.push(Issue::new(table, &path, "no chunk metadata".to_owned())),
+            Some(count) if *count != Some(rows[&key] as i64) => issues.push(Issue::new(
+                table,
+                &path,
+                format!(
+                    "metadata has {} rows, got {}",
+                    count.map(|c| c.to_string()).unwrap_or("null".to_owned()),
+                    rows[&key]
+                ),
+            )),
+            _ => (),
+        }
+    }
+    Ok(())
+}
+
+fn verify_codes(
+    table: &str,
+    path: &Path,
+    df: &DataFrame,
+    enums: &[String],
+    syms: usize,
+    issues: &mut Vec<Issue>,
+) -> JResult<()> {
+    for name in enums {
+        let Ok(column) = df.column(name) else {
+            continue;
+        };
+        if column.dtype() != &DataType::UInt32 {
+            issues.push(Issue::new(
+                table,
+                path,
+                format!("'{}' is not enumerated, got {}", name, column.dtype()),
+            ));
+        } else if let Some(code) = column
+            .u32()?
+            .into_iter()
+            .flatten()
+            .max()
+            .filter(|code| *code as usize >= syms)
+        {
+            issues.push(Issue::new(
+                table,
+                path,
+                format!("'{}' has unknown symbol code {}", name, code),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn remove_tmp(storage: &dyn Storage, dir: &Path) -> JResult<usize> {
+    let mut removed = 0;
+    for name in storage.list(dir)? {
+        if is_tmp(&name) {
+            storage.delete(&dir.join(name))?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+// merges chunks of each partition into one chunk sorted by its recorded sort series, published
+// as a new version, and removes orphan temp files of chunks and versions, returns merged chunks
+pub fn compact_db(storage: &Arc<dyn Storage>, db_path: &Path) -> JResult<Vec<PathBuf>> {
+    let tables = load_db(storage, db_path)?;
+    {
+        // symbol dictionaries are the only files written to the database directory
+        let _lock = FileLock::acquire(storage.as_ref(), &db_path.join(SYM_LOCK_FILE))?;
+        remove_tmp(storage.as_ref(), db_path)?;
+    }
+    let mut merged = Vec::new();
+    for (_, j) in tables {
+        let J::Parted(parted) = j else {
+            continue;
+        };
+        if parted.scheme.unit == PartitionUnit::Single {
+            continue;
+        }
+        let _lock = FileLock::acquire(storage.as_ref(), &parted.path.join(LOCK_FILE))?;
+        remove_tmp(storage.as_ref(), &parted.path)?;
+        remove_manifest_tmp(storage.as_ref(), &parted.path)?;
+        // writers may have published versions since the database was loaded
+        let Some(parted) = load_table(storage, &parted.path)? else {
+            continue;
+        };
+        let meta = read_meta(storage.as_ref(), &parted.path)?;
+        for partition in parted.partitions.iter() {
+            let chunks = list_chunks(storage.as_ref(), &parted.path, *partition)?;
+            if chunks.len() < 2 {
+                continue;
+            }
+            let sort_series = recorded_sort(meta.as_ref(), *partition, &chunks)?;
+            merged.push(merge_chunks(
+                storage.as_ref(),
+                &parted.path,
+                *partition,
+                &chunks,
+                &sort_series,
+                parted.scheme.format,
+            )?);
+        }
+    }
+    Ok(merged)
+}
@@ -0,0 +1,332 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use polars::frame::DataFrame;
+use polars::prelude::{BooleanChunked, DataType, SortMultipleOptions};
+
+use crate::db::format::{write_file_atomic, TableFormat};
+use crate::db::loader::load_table;
+use crate::db::lock::{FileLock, LOCK_FILE};
+use crate::db::manifest::Manifest;
+use crate::db::meta::{chunk_meta, update_meta};
+use crate::db::parted::PartitionUnit;
+use crate::db::schema::scan_unified;
+use crate::db::scheme::{PartitionScheme, SCHEME_FILE};
+use crate::db::storage::Storage;
+use crate::db::sym::enumerate_df;
+use crate::errors::{JError, JResult};
+
+// written to a hidden temp file first, then renamed to the target path
+pub const TMP_SUFFIX: &str = ".tmp";
+
+pub fn tmp_path(path: &Path) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!(".{}{}", name, TMP_SUFFIX))
+}
+
+// <partition>_<nnnn>
+pub fn chunk_name(partition: i64, chunk: usize) -> String {
+    format!("{}_{:04}", partition, chunk)
+}
+
+// <partition>_<nnnn> -> (partition, chunk)
+pub fn parse_chunk(name: &str) -> Option<(i64, usize)> {
+    let (partition, chunk) = name.split_once('_')?;
+    if partition.is_empty()
+        || !partition.chars().all(|c| c.is_ascii_digit())
+        || chunk.len() != 4
+        || !chunk.chars().all(|c| c.is_ascii_digit())
+    {
+        return None;
+    }
+    Some((partition.parse().ok()?, chunk.parse().ok()?))
+}
+
+// chunk numbers of a partition in the latest version, in scan order
+pub fn list_chunks(
+    storage: &dyn Storage,
+    table_path: &Path,
+    partition: i64,
+) -> JResult<Vec<usize>> {
+    Ok(Manifest::read(storage, table_path, None)?.chunks_of(partition))
+}
+
+// smallest chunk number of a partition not used by any file, files of kept versions are
+// never replaced
+pub fn free_chunk(storage: &dyn Storage, table_path: &Path, partition: i64) -> JResult<usize> {
+    let mut used = HashSet::new();
+    if storage.is_dir(table_path) {
+        for name in storage.list(table_path)? {
+            match parse_chunk(&name) {
+                Some((p, chunk)) if p == partition => used.insert(chunk),
+                _ => continue,
+            };
+        }
+    }
+    Ok((0..)
+        .find(|chunk| !used.contains(chunk))
+        .unwrap_or_default())
+}
+
+pub fn sort_df(df: &DataFrame, sort_series: &[String]) -> JResult<DataFrame> {
+    if sort_series.is_empty() {
+        Ok(df.clone())
+    } else {
+        Ok(df.sort(sort_series.to_vec(), SortMultipleOptions::default())?)
+    }
+}
+
+// records the scheme of a new table, or checks it against the recorded or inferred one,
+// int partitions are accepted by year and month tables, enumerated series are merged,
+// the format of a table is kept once recorded
+pub fn record_scheme(
+    storage: &Arc<dyn Storage>,
+    table_path: &Path,
+    scheme: &PartitionScheme,
+) -> JResult<()> {
+    let existing = match PartitionScheme::read(storage.as_ref(), table_path)? {
+        Some(existing) => Some(existing),
+        None => load_table(storage, table_path)?.map(|parted| parted.scheme),
+    };
+    match existing {
+        Some(existing) if existing.format != scheme.format => Err(JError::Err(format!(
+            "'{}' is stored as {}, got {}",
+            table_path.display(),
+            existing.format.name(),
+            scheme.format.name()
+        ))),
+        Some(mut existing)
+            if existing.is_same_partition(scheme)
+                || (scheme.unit == PartitionUnit::Int
+                    && matches!(existing.unit, PartitionUnit::Year | PartitionUnit::Month)) =>
+        {
+            let size = existing.enums.len();
+            for name in scheme.enums.iter() {
+                if !existing.enums.contains(name) {
+                    existing.enums.push(name.clone());
+                }
+            }
+            if existing.enums.len() > size || !storage.is_file(&table_path.join(SCHEME_FILE)) {
+                existing.write(storage.as_ref(), table_path)?;
+            }
+            Ok(())
+        }
+        Some(existing) => Err(JError::Err(format!(
+            "'{}' is partitioned by {}, got {}",
+            table_path.display(),
+            existing.series,
+            scheme.series
+        ))),
+        None => scheme.write(storage.as_ref(), table_path),
+    }
+}
+
+// writes 'table/<partition>_<nnnn>' for a partitioned table, or 'table' as a single file,
+// categorical series of partitioned tables are stored as codes of the dictionary of the table,
+// except for tables with chunks written before enumeration,
+// a chunk is written to an unused name and published as a new version of the table, then
+// chunk metadata is updated, all under the table lock
+//   overwrite: replaces all chunks of the partition with the new one
+//   rechunk: merges all chunks of the partition into a sorted one after appending
+#[allow(clippy::too_many_arguments)]
+pub fn write_partition(
+    storage: &Arc<dyn Storage>,
+    db_path: &Path,
+    table: &str,
+    scheme: &PartitionScheme,
+    partition: Option<i64>,
+    df: &DataFrame,
+    sort_series: &[String],
+    rechunk: bool,
+    overwrite: bool,
+) -> JResult<PathBuf> {
+    let table_path = db_path.join(table);
+    let partition = match partition {
+        Some(partition) if scheme.unit != PartitionUnit::Single => partition,
+        Some(partition) => {
+            return Err(JError::Err(format!(
+                "single file table, not allow partition '{}'",
+                partition
+            )))
+        }
+        None if scheme.unit != PartitionUnit::Single => {
+            return Err(JError::Err(format!(
+                "requires a partition for {} partitioned table '{}'",
+                scheme.unit.name(),
+                table
+            )))
+        }
+        None => {
+            if storage.is_dir(&table_path) {
+                return Err(JError::Err(format!(
+                    "partitioned table exists, not allow single file '{}'",
+                    table
+                )));
+            }
+            write_file_atomic(
+                storage.as_ref(),
+                &mut sort_df(df, sort_series)?,
+                &table_path,
+                scheme.format,
+            )?;
+            return Ok(table_path);
+        }
+    };
+
+    if storage.is_file(&table_path) {
+        return Err(JError::Err(format!(
+            "single file exists, not allow partition '{}'",
+            partition
+        )));
+    }
+    let _lock = FileLock::acquire(storage.as_ref(), &table_path.join(LOCK_FILE))?;
+    let mut manifest = Manifest::read(storage.as_ref(), &table_path, None)?;
+    let chunks = manifest.chunks_of(partition);
+    let recorded = if manifest.chunks.is_empty() {
+        None
+    } else {
+        Some(
+            PartitionScheme::read(storage.as_ref(), &table_path)?
+                .map(|scheme| scheme.enums)
+                .unwrap_or_default(),
+        )
+    };
+    // sorted by codes, same as merged chunks
+    let mut df = df.clone();
+    let enums = enumerate_df(
+        storage.as_ref(),
+        db_path,
+        &mut df,
+        recorded.as_deref(),
+        &mut manifest.sym,
+    )?;
+    let mut df = sort_df(&df, sort_series)?;
+    record_scheme(storage, &table_path, &scheme.clone().with_enums(enums))?;
+
+    let chunk = free_chunk(storage.as_ref(), &table_path, partition)?;
+    let part_path = table_path.join(chunk_name(partition, chunk));
+    write_file_atomic(storage.as_ref(), &mut df, &part_path, scheme.format)?;
+    let removed = if overwrite {
+        chunks.clone()
+    } else {
+        Vec::new()
+    };
+    manifest.replace(partition, &removed, &[chunk]);
+    manifest.publish(storage.as_ref(), &table_path)?;
+    update_meta(
+        storage.as_ref(),
+        &table_path,
+        &[(partition, chunk)],
+        Some(chunk_meta(partition, chunk, &df, sort_series)?),
+    )?;
+    if rechunk && !overwrite && !chunks.is_empty() {
+        let mut chunks = chunks;
+        chunks.push(chunk);
+        merge_chunks(
+            storage.as_ref(),
+            &table_path,
+            partition,
+            &chunks,
+            sort_series,
+            scheme.format,
+        )
+    } else {
+        Ok(part_path)
+    }
+}
+
+// splits rows into buckets by hashing the series as strings, nulls as empty strings,
+// then writes each bucket as a partition, overwrite only replaces written buckets
+#[allow(clippy::too_many_arguments)]
+pub fn write_hashed(
+    storage: &Arc<dyn Storage>,
+    db_path: &Path,
+    table: &str,
+    scheme: &PartitionScheme,
+    df: &DataFrame,
+    sort_series: &[String],
+    rechunk: bool,
+    overwrite: bool,
+) -> JResult<Vec<PathBuf>> {
+    if scheme.unit != PartitionUnit::Hash {
+        return Err(JError::Err(format!(
+            "requires hash partitions, got {}",
+            scheme.unit.name()
+        )));
+    }
+    let table_path = db_path.join(table);
+    if storage.is_file(&table_path) {
+        return Err(JError::Err(format!(
+            "single file exists, not allow hash partitions '{}'",
+            table
+        )));
+    }
+    let series = df.column(&scheme.series)?.cast(&DataType::String)?;
+    let buckets: Vec<i64> = series
+        .str()?
+        .into_iter()
+        .map(|s| scheme.bucket(s.unwrap_or("")))
+        .collect();
+    let mut partitions = buckets.clone();
+    partitions.sort();
+    partitions.dedup();
+    {
+        let _lock = FileLock::acquire(storage.as_ref(), &table_path.join(LOCK_FILE))?;
+        record_scheme(storage, &table_path, scheme)?;
+    }
+    let mut paths = Vec::with_capacity(partitions.len());
+    for partition in partitions {
+        let mask: BooleanChunked = buckets.iter().map(|b| *b == partition).collect();
+        paths.push(write_partition(
+            storage,
+            db_path,
+            table,
+            scheme,
+            Some(partition),
+            &df.filter(&mask)?,
+            sort_series,
+            rechunk,
+            overwrite,
+        )?);
+    }
+    Ok(paths)
+}
+
+// merges chunks into a sorted one of the format, published as a new version replacing them,
+// callers hold the table lock
+pub fn merge_chunks(
+    storage: &dyn Storage,
+    table_path: &Path,
+    partition: i64,
+    chunks: &[usize],
+    sort_series: &[String],
+    format: TableFormat,
+) -> JResult<PathBuf> {
+    if chunks.is_empty() {
+        return Err(JError::Err(format!(
+            "no chunk for partition '{}'",
+            partition
+        )));
+    }
+    let paths: Vec<PathBuf> = chunks
+        .iter()
+        .map(|chunk| table_path.join(chunk_name(partition, *chunk)))
+        .collect();
+    // chunks of different schemas are merged as the unified one
+    let mut df = sort_df(&scan_unified(storage, &paths)?.collect()?, sort_series)?;
+    df.rechunk_mut();
+    let chunk = free_chunk(storage, table_path, partition)?;
+    let part_path = table_path.join(chunk_name(partition, chunk));
+    write_file_atomic(storage, &mut df, &part_path, format)?;
+    let mut manifest = Manifest::read(storage, table_path, None)?;
+    manifest.replace(partition, chunks, &[chunk]);
+    manifest.publish(storage, table_path)?;
+    update_meta(
+        storage,
+        table_path,
+        &[(partition, chunk)],
+        Some(chunk_meta(partition, chunk, &df, sort_series)?),
+    )?;
+    Ok(part_path)
+}
@@ -1,4 +1,5 @@
 use anyhow::Result;
+use polars::error::PolarsError;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -14,6 +15,12 @@ pub enum JError {
 
     #[error("Length error '{0}' vs '{1}'")]
     MismatchedLengthErr(usize, usize),
+
+    #[error("IO err: {0}")]
+    IoErr(#[from] std::io::Error),
+
+    #[error("Polars err: {0}")]
+    PolarsErr(#[from] PolarsError),
 }
 
 pub type JResult<J> = Result<J, JError>;
@@ -1,3 +1,4 @@
+use crate::db::parted::Parted;
 use indexmap::IndexMap;
 use ndarray::ArcArray2;
 use polars::{
@@ -30,6 +31,8 @@ pub enum J {
     DataFrame(DataFrame),      // 92 -> Arrow IPC
 
     Err(String), // 128 => string
+
+    Parted(Parted), // partitioned table on disk, not serialized
 }
 
 impl J {
@@ -109,6 +112,7 @@ impl J {
             J::DataFrame(_) => "df".to_owned(),
             J::Err(_) => "err".to_owned(),
             J::None => "none".to_owned(),
+            J::Parted(_) => "parted".to_owned(),
         }
     }
 }
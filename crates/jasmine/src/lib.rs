@@ -1,7 +1,9 @@
 mod ast_node;
+pub mod db;
 pub mod errors;
 pub mod j;
 mod parser;
+pub mod serialize;
 pub use ast_node::AstNode;
 pub use errors::trace;
 pub use parser::UNIX_EPOCH_DAY;
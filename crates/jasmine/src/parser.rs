@@ -327,6 +327,39 @@ fn parse_exp(pair: Pair<Rule>, source_id: usize) -> Result<AstNode, PestError<Ru
             }
         }
         Rule::SqlExp => parse_sql(pair, source_id),
+        Rule::ExplainExp => {
+            let node = parse_sql(pair.into_inner().next().unwrap(), source_id)?;
+            Ok(AstNode::Explain(Box::new(node)))
+        }
+        Rule::Columns => {
+            let start = pair.as_span().start();
+            let mut exclude = false;
+            let mut by_type = false;
+            let mut exp = AstNode::Skip;
+            for pair in pair.into_inner() {
+                match pair.as_rule() {
+                    Rule::ColumnsExclude => exclude = true,
+                    Rule::ColumnsByType => by_type = true,
+                    _ => exp = parse_exp(pair, source_id)?,
+                }
+            }
+            Ok(AstNode::Columns {
+                exp: Box::new(exp),
+                exclude,
+                by_type,
+                start,
+                source_id,
+            })
+        }
+        Rule::Handle => {
+            let start = pair.as_span().start();
+            let exp = parse_exp(pair.into_inner().next().unwrap(), source_id)?;
+            Ok(AstNode::Handle {
+                exp: Box::new(exp),
+                start,
+                source_id,
+            })
+        }
         Rule::BracketExp => Ok(parse_exp(pair.into_inner().next().unwrap(), source_id)?),
         Rule::BracketSqlExp => {
             let pairs = pair.into_inner();
@@ -714,20 +747,39 @@ fn parse_sql(pair: Pair<Rule>, source_id: usize) -> Result<AstNode, PestError<Ru
     let mut pairs = pair.into_inner();
     // select, update, exec, delete
     let mut op = "select";
+    let mut distinct = false;
     let mut ops: Vec<AstNode> = Vec::new();
     let mut groups: Vec<AstNode> = Vec::new();
+    // named sql expressions, evaluated before from
+    let mut withs: Vec<AstNode> = Vec::new();
     let mut from: AstNode = AstNode::Skip;
+    let mut joins: Vec<AstNode> = Vec::new();
     let mut filters: Vec<AstNode> = Vec::new();
+    // filters after group or select, applied to aggregated results
+    let mut post_filters: Vec<AstNode> = Vec::new();
+    let mut is_post = false;
+    // calls of reshaping fns, the dataframe is passed as the first argument
+    let mut reshapes: Vec<AstNode> = Vec::new();
     let mut sorts: Vec<AstNode> = Vec::new();
     let mut take = AstNode::J(J::None);
     while let Some(some_pair) = pairs.next() {
         match some_pair.as_rule() {
-            Rule::SelectOp | Rule::UpdateOp | Rule::DeleteOp => {
-                op = &some_pair.as_str()[..6];
+            Rule::SelectOp | Rule::ExecOp | Rule::UpdateOp | Rule::DeleteOp => {
+                op = match some_pair.as_rule() {
+                    Rule::SelectOp => "select",
+                    Rule::ExecOp => "exec",
+                    Rule::UpdateOp => "update",
+                    _ => "delete",
+                };
                 let op_pairs = some_pair.into_inner();
                 for op_pair in op_pairs {
-                    ops.push(parse_sql_col_exp(op_pair, source_id)?)
+                    if op_pair.as_rule() == Rule::Distinct {
+                        distinct = true
+                    } else {
+                        ops.push(parse_sql_col_exp(op_pair, source_id)?)
+                    }
                 }
+                is_post = true;
             }
             Rule::GroupExp => {
                 let group_pairs = some_pair.into_inner();
@@ -735,14 +787,33 @@ fn parse_sql(pair: Pair<Rule>, source_id: usize) -> Result<AstNode, PestError<Ru
                 for group_pair in group_pairs {
                     groups.push(parse_sql_col_exp(group_pair, source_id)?)
                 }
+                is_post = true;
+            }
+            Rule::WithExp => {
+                for binding in some_pair.into_inner() {
+                    let mut binding_pairs = binding.into_inner();
+                    let id = binding_pairs.next().unwrap().as_str().to_owned();
+                    let exp = parse_sql(binding_pairs.next().unwrap(), source_id)?;
+                    withs.push(AstNode::Assign {
+                        id,
+                        exp: Box::new(exp),
+                    })
+                }
             }
             Rule::FromExp => from = parse_exp(some_pair.into_inner().next().unwrap(), source_id)?,
+            Rule::JoinExp => joins.push(parse_sql_join(some_pair, source_id)?),
             Rule::FilterExp => {
                 let filter_pairs = some_pair.into_inner();
-                filters = Vec::with_capacity(filter_pairs.len());
+                let mut nodes = Vec::with_capacity(filter_pairs.len());
                 for filter_pair in filter_pairs {
-                    filters.push(parse_exp(filter_pair, source_id)?)
+                    nodes.push(parse_exp(filter_pair, source_id)?)
                 }
+                if is_post {
+                    post_filters = nodes
+                } else {
+                    filters = nodes
+                }
+                is_post = true;
             }
             Rule::SortOp => {
                 let sort_pairs = some_pair.into_inner();
@@ -756,6 +827,26 @@ fn parse_sql(pair: Pair<Rule>, source_id: usize) -> Result<AstNode, PestError<Ru
                 }
             }
             Rule::TakeOp => take = parse_exp(some_pair.into_inner().next().unwrap(), source_id)?,
+            Rule::ReshapeOp => {
+                let span = some_pair.as_span();
+                let mut reshape_pairs = some_pair.into_inner();
+                let name = reshape_pairs.next().unwrap();
+                let mut args = Vec::with_capacity(reshape_pairs.len());
+                for arg in reshape_pairs {
+                    args.push(parse_exp(arg.into_inner().next().unwrap(), source_id)?)
+                }
+                reshapes.push(AstNode::Call {
+                    f: Box::new(AstNode::Id {
+                        name: name.as_str().to_owned(),
+                        start: name.as_span().start(),
+                        source_id,
+                    }),
+                    args,
+                    start: span.start(),
+                    source_id,
+                });
+                is_post = true;
+            }
             unexpected_exp => {
                 return Err(raise_error(
                     format!("Unexpected sql: {:?}", unexpected_exp),
@@ -766,10 +857,15 @@ fn parse_sql(pair: Pair<Rule>, source_id: usize) -> Result<AstNode, PestError<Ru
     }
     Ok(AstNode::Sql {
         op: op.to_owned(),
+        distinct,
+        withs,
         ops,
         groups,
         from: Box::new(from),
+        joins,
         filters,
+        reshapes,
+        post_filters,
         sorts,
         take: Box::new(take),
         source_id,
@@ -777,6 +873,37 @@ fn parse_sql(pair: Pair<Rule>, source_id: usize) -> Result<AstNode, PestError<Ru
     })
 }
 
+fn parse_sql_join(pair: Pair<Rule>, source_id: usize) -> Result<AstNode, PestError<Rule>> {
+    let span = pair.as_span();
+    let mut pairs = pair.into_inner();
+    let how = pairs.next().unwrap().as_str().to_owned();
+    let right = parse_exp(pairs.next().unwrap(), source_id)?;
+    let mut keys: Vec<AstNode> = Vec::new();
+    if let Some(on_pair) = pairs.next() {
+        for key_pair in on_pair.into_inner() {
+            keys.push(parse_sql_col_exp(key_pair, source_id)?)
+        }
+    }
+    if how == "union" && !keys.is_empty() {
+        return Err(raise_error(
+            "'union' join does not take join keys".to_owned(),
+            span,
+        ));
+    } else if how != "union" && keys.is_empty() {
+        return Err(raise_error(
+            format!("'{}' join requires join keys 'on {{}}'", how),
+            span,
+        ));
+    }
+    Ok(AstNode::Join {
+        how,
+        right: Box::new(right),
+        keys,
+        start: span.start(),
+        source_id,
+    })
+}
+
 fn parse_sql_col_exp(pair: Pair<Rule>, source_id: usize) -> Result<AstNode, PestError<Rule>> {
     match pair.as_rule() {
         Rule::SeriesExp => parse_sql_col_exp(pair.into_inner().next().unwrap(), source_id),
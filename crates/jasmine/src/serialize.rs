@@ -0,0 +1,354 @@
+use std::io::Cursor;
+
+use indexmap::IndexMap;
+use ndarray::Array2;
+use polars::frame::DataFrame;
+use polars::io::{SerReader, SerWriter};
+use polars::prelude::{DataType, IntoColumn, IpcReader, IpcWriter, TimeUnit};
+use polars::series::Series;
+
+use crate::errors::{JError, JResult};
+use crate::j::J;
+
+// a serialized J starts with its type code as one byte, negative codes as two's complement,
+// followed by its little endian payload
+//   -1 bool: 1 byte
+//   -5 i64, -7 time, -10 duration: i64
+//   -6 date: i32
+//   -8 datetime, -9 timestamp: i64, timezone as string
+//   -12 f64: f64
+//   -13 string, -14 cat, 128 err: u32 length, utf8 bytes
+//   0 none: nothing
+//   1-14 series, 92 dataframe: u64 length, arrow ipc file of the series as the only column
+//   21 matrix: u32 rows, u32 columns, f64 values in row-major order
+//   90 list: u32 length, serialized items
+//   91 dict: u32 length, keys as strings each followed by its serialized value
+// lists and dicts are nested up to MAX_DEPTH levels
+const BOOL: i16 = -1;
+const I64: i16 = -5;
+const DATE: i16 = -6;
+const TIME: i16 = -7;
+const DATETIME: i16 = -8;
+const TIMESTAMP: i16 = -9;
+const DURATION: i16 = -10;
+const F64: i16 = -12;
+const STRING: i16 = -13;
+const CAT: i16 = -14;
+const NONE: i16 = 0;
+const MATRIX: i16 = 21;
+const LIST: i16 = 90;
+const DICT: i16 = 91;
+const DATAFRAME: i16 = 92;
+const ERR: i16 = 128;
+
+// bounds the recursion of reading nested lists and dicts from untrusted bytes
+pub const MAX_DEPTH: usize = 64;
+
+// series codes follow scalar codes of the same type, the arrow schema keeps the exact dtype
+//   1 bool, 2 i8|u8, 3 i16|u16, 4 i32|u32, 5 i64|u64, 6 date, 7 time, 8 datetime(ms|us),
+//   9 timestamp(ns), 10 duration, 11 f32, 12 f64, 13 string, 14 cat
+fn series_code(dtype: &DataType) -> JResult<i16> {
+    match dtype {
+        DataType::Boolean => Ok(1),
+        DataType::Int8 | DataType::UInt8 => Ok(2),
+        DataType::Int16 | DataType::UInt16 => Ok(3),
+        DataType::Int32 | DataType::UInt32 => Ok(4),
+        DataType::Int64 | DataType::UInt64 => Ok(5),
+        DataType::Date => Ok(6),
+        DataType::Time => Ok(7),
+        DataType::Datetime(TimeUnit::Nanoseconds, _) => Ok(9),
+        DataType::Datetime(_, _) => Ok(8),
+        DataType::Duration(_) => Ok(10),
+        DataType::Float32 => Ok(11),
+        DataType::Float64 => Ok(12),
+        DataType::String => Ok(13),
+        DataType::Categorical(_, _) => Ok(14),
+        _ => Err(JError::Err(format!(
+            "not support to serialize {} series",
+            dtype
+        ))),
+    }
+}
+
+fn code_byte(code: i16) -> u8 {
+    (code & 0xff) as u8
+}
+
+fn byte_code(byte: u8) -> i16 {
+    if byte > 128 {
+        byte as i16 - 256
+    } else {
+        byte as i16
+    }
+}
+
+impl J {
+    pub fn serialize(&self) -> JResult<Vec<u8>> {
+        let mut bytes = Vec::new();
+        write_j(&mut bytes, self)?;
+        Ok(bytes)
+    }
+
+    // the whole input must be one serialized J
+    pub fn deserialize(bytes: &[u8]) -> JResult<J> {
+        let mut reader = Reader {
+            bytes,
+            pos: 0,
+            depth: 0,
+        };
+        let j = reader.read_j()?;
+        if reader.remaining() > 0 {
+            return Err(JError::Err(format!(
+                "invalid serialized J, {} trailing bytes",
+                reader.remaining()
+            )));
+        }
+        Ok(j)
+    }
+}
+
+fn write_len(bytes: &mut Vec<u8>, len: usize) -> JResult<()> {
+    let len = u32::try_from(len)
+        .map_err(|_| JError::Err(format!("length {} exceeds {}", len, u32::MAX)))?;
+    bytes.extend(len.to_le_bytes());
+    Ok(())
+}
+
+fn write_str(bytes: &mut Vec<u8>, s: &str) -> JResult<()> {
+    write_len(bytes, s.len())?;
+    bytes.extend(s.as_bytes());
+    Ok(())
+}
+
+fn write_ipc(bytes: &mut Vec<u8>, df: &DataFrame) -> JResult<()> {
+    let mut ipc = Vec::new();
+    IpcWriter::new(&mut ipc).finish(&mut df.clone())?;
+    bytes.extend((ipc.len() as u64).to_le_bytes());
+    bytes.extend(ipc);
+    Ok(())
+}
+
+fn write_j(bytes: &mut Vec<u8>, j: &J) -> JResult<()> {
+    match j {
+        J::Boolean(b) => {
+            bytes.push(code_byte(BOOL));
+            bytes.push(*b as u8);
+        }
+        J::I64(i) => {
+            bytes.push(code_byte(I64));
+            bytes.extend(i.to_le_bytes());
+        }
+        J::Date(d) => {
+            bytes.push(code_byte(DATE));
+            bytes.extend(d.to_le_bytes());
+        }
+        J::Time(t) => {
+            bytes.push(code_byte(TIME));
+            bytes.extend(t.to_le_bytes());
+        }
+        J::Datetime { ms, timezone } => {
+            bytes.push(code_byte(DATETIME));
+            bytes.extend(ms.to_le_bytes());
+            write_str(bytes, timezone)?;
+        }
+        J::Timestamp { ns, timezone } => {
+            bytes.push(code_byte(TIMESTAMP));
+            bytes.extend(ns.to_le_bytes());
+            write_str(bytes, timezone)?;
+        }
+        J::Duration(d) => {
+            bytes.push(code_byte(DURATION));
+            bytes.extend(d.to_le_bytes());
+        }
+        J::F64(f) => {
+            bytes.push(code_byte(F64));
+            bytes.extend(f.to_le_bytes());
+        }
+        J::String(s) => {
+            bytes.push(code_byte(STRING));
+            write_str(bytes, s)?;
+        }
+        J::Cat(s) => {
+            bytes.push(code_byte(CAT));
+            write_str(bytes, s)?;
+        }
+        J::None => bytes.push(code_byte(NONE)),
+        J::Series(s) => {
+            bytes.push(code_byte(series_code(s.dtype())?));
+            write_ipc(bytes, &DataFrame::new(vec![s.clone().into_column()])?)?;
+        }
+        J::Matrix(m) => {
+            bytes.push(code_byte(MATRIX));
+            write_len(bytes, m.nrows())?;
+            write_len(bytes, m.ncols())?;
+            for f in m.iter() {
+                bytes.extend(f.to_le_bytes());
+            }
+        }
+        J::MixedList(list) => {
+            bytes.push(code_byte(LIST));
+            write_len(bytes, list.len())?;
+            for j in list {
+                write_j(bytes, j)?;
+            }
+        }
+        J::Dict(dict) => {
+            bytes.push(code_byte(DICT));
+            write_len(bytes, dict.len())?;
+            for (k, v) in dict {
+                write_str(bytes, k)?;
+                write_j(bytes, v)?;
+            }
+        }
+        J::DataFrame(df) => {
+            bytes.push(code_byte(DATAFRAME));
+            write_ipc(bytes, df)?;
+        }
+        J::Err(s) => {
+            bytes.push(code_byte(ERR));
+            write_str(bytes, s)?;
+        }
+        J::Parted(_) => {
+            return Err(JError::Err(
+                "not support to serialize partitioned table".to_owned(),
+            ))
+        }
+    }
+    Ok(())
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    // nested lists and dicts being read
+    depth: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn remaining(&self) -> usize {
+        self.bytes.len() - self.pos
+    }
+
+    // lengths are not trusted, checked against remaining bytes before allocating
+    fn take(&mut self, n: usize) -> JResult<&'a [u8]> {
+        if self.remaining() < n {
+            return Err(JError::Err(format!(
+                "invalid serialized J, expect {} bytes at {}, got {}",
+                n,
+                self.pos,
+                self.remaining()
+            )));
+        }
+        let bytes = &self.bytes[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    fn read<const N: usize>(&mut self) -> JResult<[u8; N]> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn read_len(&mut self) -> JResult<usize> {
+        Ok(u32::from_le_bytes(self.read()?) as usize)
+    }
+
+    fn read_i64(&mut self) -> JResult<i64> {
+        Ok(i64::from_le_bytes(self.read()?))
+    }
+
+    fn read_str(&mut self) -> JResult<String> {
+        let len = self.read_len()?;
+        String::from_utf8(self.take(len)?.to_vec())
+            .map_err(|e| JError::Err(format!("invalid serialized J, {}", e)))
+    }
+
+    fn read_ipc(&mut self) -> JResult<DataFrame> {
+        let len = u64::from_le_bytes(self.read()?) as usize;
+        Ok(IpcReader::new(Cursor::new(self.take(len)?)).finish()?)
+    }
+
+    fn read_series(&mut self) -> JResult<Series> {
+        let df = self.read_ipc()?;
+        match df.get_columns() {
+            [column] => Ok(column.as_materialized_series().clone()),
+            columns => Err(JError::Err(format!(
+                "invalid serialized series, got {} columns",
+                columns.len()
+            ))),
+        }
+    }
+
+    fn enter(&mut self) -> JResult<()> {
+        if self.depth >= MAX_DEPTH {
+            return Err(JError::Err(format!(
+                "invalid serialized J, exceeds max depth {}",
+                MAX_DEPTH
+            )));
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn read_j(&mut self) -> JResult<J> {
+        let code = byte_code(self.read::<1>()?[0]);
+        match code {
+            BOOL => Ok(J::Boolean(self.read::<1>()?[0] != 0)),
+            I64 => Ok(J::I64(self.read_i64()?)),
+            DATE => Ok(J::Date(i32::from_le_bytes(self.read()?))),
+            TIME => Ok(J::Time(self.read_i64()?)),
+            DATETIME => Ok(J::Datetime {
+                ms: self.read_i64()?,
+                timezone: self.read_str()?,
+            }),
+            TIMESTAMP => Ok(J::Timestamp {
+                ns: self.read_i64()?,
+                timezone: self.read_str()?,
+            }),
+            DURATION => Ok(J::Duration(self.read_i64()?)),
+            F64 => Ok(J::F64(f64::from_le_bytes(self.read()?))),
+            STRING => Ok(J::String(self.read_str()?)),
+            CAT => Ok(J::Cat(self.read_str()?)),
+            NONE => Ok(J::None),
+            1..=14 => Ok(J::Series(self.read_series()?)),
+            MATRIX => {
+                let rows = self.read_len()?;
+                let cols = self.read_len()?;
+                let values = self
+                    .take(rows.saturating_mul(cols).saturating_mul(8))?
+                    .chunks_exact(8)
+                    .map(|f| f64::from_le_bytes(f.try_into().unwrap()))
+                    .collect();
+                let matrix = Array2::from_shape_vec((rows, cols), values)
+                    .map_err(|e| JError::Err(format!("invalid serialized matrix, {}", e)))?;
+                Ok(J::Matrix(matrix.into_shared()))
+            }
+            LIST => {
+                self.enter()?;
+                let len = self.read_len()?;
+                let mut list = Vec::with_capacity(len.min(self.remaining()));
+                for _ in 0..len {
+                    list.push(self.read_j()?);
+                }
+                self.depth -= 1;
+                Ok(J::MixedList(list))
+            }
+            DICT => {
+                self.enter()?;
+                let len = self.read_len()?;
+                let mut dict = IndexMap::with_capacity(len.min(self.remaining()));
+                for _ in 0..len {
+                    let k = self.read_str()?;
+                    dict.insert(k, self.read_j()?);
+                }
+                self.depth -= 1;
+                Ok(J::Dict(dict))
+            }
+            DATAFRAME => Ok(J::DataFrame(self.read_ipc()?)),
+            ERR => Ok(J::Err(self.read_str()?)),
+            code => Err(JError::Err(format!(
+                "invalid serialized J, unknown type code {}",
+                code
+            ))),
+        }
+    }
+}
@@ -0,0 +1,1240 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use jasmine::db::format::{read_file, write_file_atomic, TableFormat};
+use jasmine::db::loader::{load_db, load_snapshot};
+use jasmine::db::lock::{FileLock, LOCK_FILE};
+use jasmine::db::manifest::{list_versions, KEPT_VERSIONS, MANIFEST_DIR};
+use jasmine::db::meta::read_meta;
+use jasmine::db::parted::{Parted, PartitionUnit};
+use jasmine::db::schema::widen_dtype;
+use jasmine::db::scheme::PartitionScheme;
+use jasmine::db::storage::{LocalStorage, MemoryStorage, Storage};
+use jasmine::db::sym::{compact_syms, SymDict};
+use jasmine::db::verify::{compact_db, verify_db};
+use jasmine::db::writer::{list_chunks, write_hashed, write_partition};
+use jasmine::j::J;
+use polars::df;
+use polars::prelude::{CategoricalOrdering, DataType, IntoColumn};
+
+fn temp_db(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("jasmine_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&path);
+    path
+}
+
+fn local() -> Arc<dyn Storage> {
+    Arc::new(LocalStorage)
+}
+
+fn date_scheme() -> PartitionScheme {
+    PartitionScheme::new(PartitionUnit::Date)
+}
+
+fn file_names(path: &PathBuf) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(path)
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn write_partition_chunks() {
+    let db = temp_db("write_partition_chunks");
+    let storage = local();
+    let sort = vec!["sym".to_owned()];
+    let df1 = df!("sym" => ["b", "a"], "qty" => [1i64, 2]).unwrap();
+    let df2 = df!("sym" => ["c", "a"], "qty" => [3i64, 4]).unwrap();
+
+    let path = write_partition(
+        &storage,
+        &db,
+        "trade",
+        &date_scheme(),
+        Some(20240102),
+        &df1,
+        &sort,
+        false,
+        true,
+    )
+    .unwrap();
+    assert_eq!(path, db.join("trade").join("20240102_0000"));
+    let path = write_partition(
+        &storage,
+        &db,
+        "trade",
+        &date_scheme(),
+        Some(20240102),
+        &df2,
+        &sort,
+        false,
+        false,
+    )
+    .unwrap();
+    assert_eq!(path, db.join("trade").join("20240102_0001"));
+    assert_eq!(
+        list_chunks(storage.as_ref(), &db.join("trade"), 20240102).unwrap(),
+        vec![0, 1]
+    );
+    assert_eq!(
+        read_file(storage.as_ref(), &path).unwrap(),
+        df!("sym" => ["a", "c"], "qty" => [4i64, 3]).unwrap()
+    );
+
+    // rechunk merges all chunks into a sorted new chunk, replaced chunks are kept for readers
+    // of older versions
+    let path = write_partition(
+        &storage,
+        &db,
+        "trade",
+        &date_scheme(),
+        Some(20240102),
+        &df1,
+        &sort,
+        true,
+        false,
+    )
+    .unwrap();
+    assert_eq!(path, db.join("trade").join("20240102_0003"));
+    assert_eq!(
+        list_chunks(storage.as_ref(), &db.join("trade"), 20240102).unwrap(),
+        vec![3]
+    );
+    assert_eq!(
+        file_names(&db.join("trade")),
+        vec![
+            ".manifest",
+            ".meta",
+            ".scheme",
+            "20240102_0000",
+            "20240102_0001",
+            "20240102_0002",
+            "20240102_0003"
+        ]
+    );
+    let df = read_file(storage.as_ref(), &path).unwrap();
+    assert_eq!(df.height(), 6);
+    assert_eq!(df.column("sym").unwrap().str().unwrap().get(5), Some("c"));
+
+    // overwrite replaces all chunks
+    write_partition(
+        &storage,
+        &db,
+        "trade",
+        &date_scheme(),
+        Some(20240102),
+        &df2,
+        &sort,
+        false,
+        false,
+    )
+    .unwrap();
+    let path = write_partition(
+        &storage,
+        &db,
+        "trade",
+        &date_scheme(),
+        Some(20240102),
+        &df2,
+        &sort,
+        false,
+        true,
+    )
+    .unwrap();
+    assert_eq!(
+        list_chunks(storage.as_ref(), &db.join("trade"), 20240102).unwrap(),
+        vec![5]
+    );
+    assert_eq!(read_file(storage.as_ref(), &path).unwrap().height(), 2);
+    fs::remove_dir_all(&db).unwrap();
+}
+
+#[test]
+fn write_single_file() {
+    let db = temp_db("write_single_file");
+    let storage = local();
+    let df = df!("sym" => ["b", "a"]).unwrap();
+    let path = write_partition(
+        &storage,
+        &db,
+        "ref",
+        &PartitionScheme::new(PartitionUnit::Single),
+        None,
+        &df,
+        &[],
+        false,
+        true,
+    )
+    .unwrap();
+    assert_eq!(path, db.join("ref"));
+    assert_eq!(read_file(storage.as_ref(), &path).unwrap(), df);
+    assert!(write_partition(
+        &storage,
+        &db,
+        "ref",
+        &date_scheme(),
+        Some(20240102),
+        &df,
+        &[],
+        false,
+        true
+    )
+    .is_err());
+    assert_eq!(file_names(&db), vec!["ref"]);
+    fs::remove_dir_all(&db).unwrap();
+}
+
+#[test]
+fn load_partitioned_db() {
+    let db = temp_db("load_partitioned_db");
+    let storage = local();
+    let df = df!("sym" => ["a", "b"], "qty" => [1i64, 2]).unwrap();
+    for date in [20240103, 20240102] {
+        write_partition(
+            &storage,
+            &db,
+            "trade",
+            &date_scheme(),
+            Some(date),
+            &df,
+            &[],
+            false,
+            false,
+        )
+        .unwrap();
+    }
+    write_partition(
+        &storage,
+        &db,
+        "trade",
+        &date_scheme(),
+        Some(20240103),
+        &df,
+        &[],
+        false,
+        false,
+    )
+    .unwrap();
+    write_partition(
+        &storage,
+        &db,
+        "ref",
+        &PartitionScheme::new(PartitionUnit::Single),
+        None,
+        &df,
+        &[],
+        false,
+        true,
+    )
+    .unwrap();
+    fs::write(db.join("trade").join(".20240104_0000.tmp"), "").unwrap();
+
+    let tables = load_db(&storage, &db).unwrap();
+    assert_eq!(tables.keys().collect::<Vec<_>>(), vec!["ref", "trade"]);
+    let trade = Parted::new(
+        storage.clone(),
+        &db.join("trade"),
+        date_scheme(),
+        vec![20240102, 20240103],
+    );
+    assert_eq!(tables["trade"], J::Parted(trade.clone()));
+    assert_eq!(
+        tables["ref"],
+        J::Parted(Parted::new(
+            storage.clone(),
+            &db.join("ref"),
+            PartitionScheme::new(PartitionUnit::Single),
+            vec![]
+        ))
+    );
+
+    let scan = |partitions: &[i64]| {
+        trade
+            .scan(Some(partitions))
+            .unwrap()
+            .collect()
+            .unwrap()
+            .height()
+    };
+    assert_eq!(scan(&[20240103]), 4);
+    assert_eq!(scan(&[20240101, 20240102]), 2);
+    assert_eq!(scan(&[20240105]), 0);
+    assert_eq!(trade.scan(None).unwrap().collect().unwrap().height(), 6);
+    fs::remove_dir_all(&db).unwrap();
+}
+
+#[test]
+fn widen_dtypes() {
+    assert_eq!(
+        widen_dtype(&DataType::Int32, &DataType::Int64),
+        Some(DataType::Int64)
+    );
+    assert_eq!(
+        widen_dtype(&DataType::UInt32, &DataType::Int16),
+        Some(DataType::Int64)
+    );
+    assert_eq!(
+        widen_dtype(&DataType::Int64, &DataType::Float32),
+        Some(DataType::Float64)
+    );
+    assert_eq!(
+        widen_dtype(&DataType::Null, &DataType::String),
+        Some(DataType::String)
+    );
+    assert_eq!(widen_dtype(&DataType::UInt64, &DataType::Int64), None);
+    assert_eq!(widen_dtype(&DataType::String, &DataType::Int64), None);
+}
+
+#[test]
+fn scan_reconciled_partitions() {
+    let db = temp_db("scan_reconciled_partitions");
+    let storage = local();
+    let df1 = df!("sym" => ["a"], "qty" => [1i32]).unwrap();
+    let df2 = df!("sym" => ["b"], "qty" => [2i64], "price" => [1.5]).unwrap();
+    write_partition(
+        &storage,
+        &db,
+        "trade",
+        &date_scheme(),
+        Some(20240102),
+        &df1,
+        &[],
+        false,
+        true,
+    )
+    .unwrap();
+    write_partition(
+        &storage,
+        &db,
+        "trade",
+        &date_scheme(),
+        Some(20240103),
+        &df2,
+        &[],
+        false,
+        true,
+    )
+    .unwrap();
+    let trade = Parted::new(
+        storage.clone(),
+        &db.join("trade"),
+        date_scheme(),
+        vec![20240102, 20240103],
+    );
+    assert_eq!(
+        trade.scan(None).unwrap().collect().unwrap(),
+        df!(
+            "sym" => ["a", "b"],
+            "qty" => [1i64, 2],
+            "price" => [None, Some(1.5)]
+        )
+        .unwrap()
+    );
+
+    let df3 = df!("sym" => ["c"], "qty" => ["3"]).unwrap();
+    write_partition(
+        &storage,
+        &db,
+        "trade",
+        &date_scheme(),
+        Some(20240104),
+        &df3,
+        &[],
+        false,
+        true,
+    )
+    .unwrap();
+    let trade = Parted::new(
+        storage.clone(),
+        &db.join("trade"),
+        date_scheme(),
+        vec![20240102, 20240103, 20240104],
+    );
+    let err = trade.scan(None).err().unwrap().to_string();
+    assert!(err.contains("20240104_0000"), "{}", err);
+    assert!(trade.scan(Some(&[20240102, 20240103])).is_ok());
+    fs::remove_dir_all(&db).unwrap();
+}
+
+#[test]
+fn write_partition_schemes() {
+    let db = temp_db("write_partition_schemes");
+    let storage = local();
+    let df = df!("sym" => ["a", "b", "c", "a"], "qty" => [1i64, 2, 3, 4]).unwrap();
+    let month = PartitionScheme::new(PartitionUnit::Month);
+    write_partition(
+        &storage,
+        &db,
+        "bar",
+        &month,
+        Some(202401),
+        &df,
+        &[],
+        false,
+        true,
+    )
+    .unwrap();
+    // int partitions are accepted by month tables, other units are not
+    let int = PartitionScheme::new(PartitionUnit::Int);
+    write_partition(
+        &storage,
+        &db,
+        "bar",
+        &int,
+        Some(202402),
+        &df,
+        &[],
+        false,
+        true,
+    )
+    .unwrap();
+    assert!(write_partition(
+        &storage,
+        &db,
+        "bar",
+        &date_scheme(),
+        Some(20240301),
+        &df,
+        &[],
+        false,
+        true
+    )
+    .is_err());
+
+    let hash = PartitionScheme::hash("sym", 2).unwrap();
+    let paths = write_hashed(&storage, &db, "trade", &hash, &df, &[], false, true).unwrap();
+    let rows: usize = paths
+        .iter()
+        .map(|p| read_file(storage.as_ref(), p).unwrap().height())
+        .sum();
+    assert_eq!(rows, 4);
+    let a = db.join("trade").join(format!("{}_0000", hash.bucket("a")));
+    let a_df = read_file(storage.as_ref(), &a).unwrap();
+    assert!(a_df
+        .column("sym")
+        .unwrap()
+        .str()
+        .unwrap()
+        .into_iter()
+        .any(|s| s == Some("a")));
+    assert!(write_partition(&storage, &db, "trade", &int, Some(1), &df, &[], false, true).is_err());
+
+    let tables = load_db(&storage, &db).unwrap();
+    match &tables["bar"] {
+        J::Parted(parted) => {
+            assert_eq!(parted.scheme, month);
+            assert_eq!(parted.partitions, vec![202401, 202402]);
+            assert_eq!(parted.get_unit(), "month");
+        }
+        j => panic!("unexpected {:?}", j),
+    }
+    match &tables["trade"] {
+        J::Parted(parted) => {
+            assert_eq!(parted.scheme, hash);
+            assert_eq!(parted.get_unit(), "sym");
+            assert_eq!(parted.scan(None).unwrap().collect().unwrap().height(), 4);
+        }
+        j => panic!("unexpected {:?}", j),
+    }
+    fs::remove_dir_all(&db).unwrap();
+}
+
+#[test]
+fn enumerate_categorical_series() {
+    let db = temp_db("enumerate_categorical_series");
+    let storage = local();
+    let cat = DataType::Categorical(None, CategoricalOrdering::Physical);
+    let mut df1 = df!("sym" => ["b", "a", "b"], "qty" => [1i64, 2, 3]).unwrap();
+    df1.apply("sym", |s| s.cast(&cat).unwrap()).unwrap();
+    let mut df2 = df!("sym" => ["c", "a"], "qty" => [4i64, 5]).unwrap();
+    df2.apply("sym", |s| s.cast(&cat).unwrap()).unwrap();
+    let sort = vec!["sym".to_owned()];
+    write_partition(
+        &storage,
+        &db,
+        "trade",
+        &date_scheme(),
+        Some(20240102),
+        &df1,
+        &sort,
+        false,
+        true,
+    )
+    .unwrap();
+    let path = write_partition(
+        &storage,
+        &db,
+        "trade",
+        &date_scheme(),
+        Some(20240103),
+        &df2,
+        &sort,
+        false,
+        true,
+    )
+    .unwrap();
+
+    // codes are shared across partitions
+    let dict = SymDict::latest(storage.as_ref(), &db).unwrap();
+    assert_eq!(dict.len(), 3);
+    assert_eq!(
+        read_file(storage.as_ref(), &path)
+            .unwrap()
+            .column("sym")
+            .unwrap()
+            .dtype(),
+        &DataType::UInt32
+    );
+
+    let trade = match &load_db(&storage, &db).unwrap()["trade"] {
+        J::Parted(parted) => parted.clone(),
+        j => panic!("unexpected {:?}", j),
+    };
+    assert_eq!(trade.scheme.enums, vec!["sym"]);
+    let df = trade.scan(None).unwrap().collect().unwrap();
+    assert!(matches!(
+        df.column("sym").unwrap().dtype(),
+        DataType::Categorical(_, _)
+    ));
+    assert_eq!(
+        df.column("sym").unwrap().cast(&DataType::String).unwrap(),
+        ["b", "b", "a", "a", "c"]
+            .iter()
+            .collect::<polars::prelude::StringChunked>()
+            .into_column()
+            .with_name("sym".into())
+    );
+
+    // 'c' is unused after overwriting
+    write_partition(
+        &storage,
+        &db,
+        "trade",
+        &date_scheme(),
+        Some(20240103),
+        &df1,
+        &sort,
+        false,
+        true,
+    )
+    .unwrap();
+    let pinned = load_snapshot(&storage, &db.join("trade"), 3).unwrap();
+    assert_eq!(compact_syms(&storage, &db).unwrap(), 1);
+    let dict = SymDict::latest(storage.as_ref(), &db).unwrap();
+    assert_eq!((dict.name().as_str(), dict.len()), (".sym_00000001", 2));
+    let to_strings = |df: &polars::frame::DataFrame| -> Vec<String> {
+        df.column("sym")
+            .unwrap()
+            .cast(&DataType::String)
+            .unwrap()
+            .str()
+            .unwrap()
+            .into_no_null_iter()
+            .map(|s| s.to_owned())
+            .collect()
+    };
+    let df = trade.scan(None).unwrap().collect().unwrap();
+    assert_eq!(to_strings(&df), ["b", "b", "a", "b", "b", "a"]);
+    // chunks are not replaced, readers pinned to older versions decode their own dictionary
+    assert_eq!(
+        list_versions(storage.as_ref(), &db.join("trade")).unwrap(),
+        [1, 2, 3, 4]
+    );
+    assert!(db.join(".sym").exists());
+    let df = pinned.scan(None).unwrap().collect().unwrap();
+    assert_eq!(to_strings(&df), ["b", "b", "a", "b", "b", "a"]);
+    let df = load_snapshot(&storage, &db.join("trade"), 2)
+        .unwrap()
+        .scan(None)
+        .unwrap()
+        .collect()
+        .unwrap();
+    assert_eq!(to_strings(&df), ["b", "b", "a", "a", "c"]);
+    assert!(verify_db(&storage, &db).unwrap().is_empty());
+    assert_eq!(compact_syms(&storage, &db).unwrap(), 0);
+
+    // codes of new rows refer to the compacted dictionary
+    write_partition(
+        &storage,
+        &db,
+        "trade",
+        &date_scheme(),
+        Some(20240104),
+        &df2,
+        &sort,
+        false,
+        true,
+    )
+    .unwrap();
+    let dict = SymDict::latest(storage.as_ref(), &db).unwrap();
+    assert_eq!((dict.name().as_str(), dict.len()), (".sym_00000001", 3));
+    let trade = match &load_db(&storage, &db).unwrap()["trade"] {
+        J::Parted(parted) => parted.clone(),
+        j => panic!("unexpected {:?}", j),
+    };
+    let df = trade.scan(None).unwrap().collect().unwrap();
+    assert_eq!(to_strings(&df), ["b", "b", "a", "b", "b", "a", "a", "c"]);
+    fs::remove_dir_all(&db).unwrap();
+}
+
+#[test]
+fn append_legacy_categorical() {
+    let db = temp_db("append_legacy_categorical");
+    let storage = local();
+    let cat = DataType::Categorical(None, CategoricalOrdering::Physical);
+    // a chunk written before enumeration keeps categorical series
+    let mut df = df!("sym" => ["b", "a"], "qty" => [1i64, 2]).unwrap();
+    df.apply("sym", |s| s.cast(&cat).unwrap()).unwrap();
+    write_file_atomic(
+        storage.as_ref(),
+        &mut df.clone(),
+        &db.join("trade").join("20240102_0000"),
+        TableFormat::Parquet,
+    )
+    .unwrap();
+    write_partition(
+        &storage,
+        &db,
+        "trade",
+        &date_scheme(),
+        Some(20240103),
+        &df,
+        &[],
+        false,
+        false,
+    )
+    .unwrap();
+    let string = df!("sym" => ["c"], "qty" => [3i64]).unwrap();
+    let path = write_partition(
+        &storage,
+        &db,
+        "trade",
+        &date_scheme(),
+        Some(20240103),
+        &string,
+        &[],
+        false,
+        false,
+    )
+    .unwrap();
+    assert_eq!(
+        read_file(storage.as_ref(), &path)
+            .unwrap()
+            .column("sym")
+            .unwrap()
+            .dtype(),
+        &DataType::String
+    );
+    assert!(!db.join(".sym").exists());
+
+    let trade = match &load_db(&storage, &db).unwrap()["trade"] {
+        J::Parted(parted) => parted.clone(),
+        j => panic!("unexpected {:?}", j),
+    };
+    assert!(trade.scheme.enums.is_empty());
+    let scanned = trade.scan(None).unwrap().collect().unwrap();
+    assert_eq!(
+        scanned
+            .column("sym")
+            .unwrap()
+            .cast(&DataType::String)
+            .unwrap()
+            .str()
+            .unwrap()
+            .into_no_null_iter()
+            .collect::<Vec<_>>(),
+        ["b", "a", "b", "a", "c"]
+    );
+    // only the chunk written without the writer lacks metadata
+    let issues = verify_db(&storage, &db).unwrap();
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].message, "no chunk metadata");
+
+    // string series of enumerated tables are enumerated
+    let hash = PartitionScheme::hash("sym", 1).unwrap();
+    write_hashed(&storage, &db, "quote", &hash, &df, &[], false, false).unwrap();
+    write_hashed(&storage, &db, "quote", &hash, &string, &[], false, false).unwrap();
+    let quote = match &load_db(&storage, &db).unwrap()["quote"] {
+        J::Parted(parted) => parted.clone(),
+        j => panic!("unexpected {:?}", j),
+    };
+    assert_eq!(quote.scheme.enums, ["sym"]);
+    assert_eq!(quote.scan(None).unwrap().collect().unwrap().height(), 3);
+    assert_eq!(verify_db(&storage, &db).unwrap(), issues);
+    fs::remove_dir_all(&db).unwrap();
+}
+
+#[test]
+fn record_chunk_meta() {
+    let db = temp_db("record_chunk_meta");
+    let storage = local();
+    let sort = vec!["sym".to_owned()];
+    let df1 = df!("sym" => ["b", "a"], "qty" => [1i64, 2]).unwrap();
+    let df2 = df!("sym" => ["c"], "qty" => [3i64], "price" => [1.5]).unwrap();
+    write_partition(
+        &storage,
+        &db,
+        "trade",
+        &date_scheme(),
+        Some(20240102),
+        &df1,
+        &sort,
+        false,
+        true,
+    )
+    .unwrap();
+    write_partition(
+        &storage,
+        &db,
+        "trade",
+        &date_scheme(),
+        Some(20240103),
+        &df1,
+        &[],
+        false,
+        true,
+    )
+    .unwrap();
+    write_partition(
+        &storage,
+        &db,
+        "trade",
+        &date_scheme(),
+        Some(20240103),
+        &df2,
+        &[],
+        false,
+        false,
+    )
+    .unwrap();
+
+    let table = db.join("trade");
+    let meta = read_meta(storage.as_ref(), &table).unwrap().unwrap();
+    assert_eq!(meta.height(), 3);
+    let i64s = |name: &str| {
+        meta.column(name)
+            .unwrap()
+            .i64()
+            .unwrap()
+            .into_iter()
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        i64s("partition"),
+        vec![Some(20240102), Some(20240103), Some(20240103)]
+    );
+    assert_eq!(i64s("chunk"), vec![Some(0), Some(0), Some(1)]);
+    assert_eq!(i64s("rows"), vec![Some(2), Some(2), Some(1)]);
+    assert_eq!(i64s("qty.max"), vec![Some(2), Some(2), Some(3)]);
+    assert_eq!(
+        meta.column("sort").unwrap().str().unwrap().get(0),
+        Some("sym")
+    );
+    assert_eq!(
+        meta.column("sym.min").unwrap().str().unwrap().get(2),
+        Some("c")
+    );
+    assert_eq!(
+        meta.column("price.min").unwrap().f64().unwrap().get(1),
+        None
+    );
+    assert!(meta
+        .column("schema")
+        .unwrap()
+        .str()
+        .unwrap()
+        .get(2)
+        .unwrap()
+        .contains("price"));
+
+    // rechunk and overwrite add one row for the partition, rows of replaced chunks are kept
+    // with their files for pinned readers
+    write_partition(
+        &storage,
+        &db,
+        "trade",
+        &date_scheme(),
+        Some(20240103),
+        &df1,
+        &sort,
+        true,
+        false,
+    )
+    .unwrap();
+    let meta = read_meta(storage.as_ref(), &table).unwrap().unwrap();
+    assert_eq!(meta.height(), 5);
+    assert_eq!(meta.column("chunk").unwrap().i64().unwrap().get(4), Some(3));
+    assert_eq!(meta.column("rows").unwrap().i64().unwrap().get(4), Some(5));
+    write_partition(
+        &storage,
+        &db,
+        "trade",
+        &date_scheme(),
+        Some(20240102),
+        &df2,
+        &[],
+        false,
+        true,
+    )
+    .unwrap();
+    let meta = read_meta(storage.as_ref(), &table).unwrap().unwrap();
+    assert_eq!(meta.height(), 6);
+    assert_eq!(meta.column("chunk").unwrap().i64().unwrap().get(1), Some(1));
+    assert_eq!(meta.column("rows").unwrap().i64().unwrap().get(1), Some(1));
+    assert_eq!(meta.column("sort").unwrap().str().unwrap().get(1), Some(""));
+    fs::remove_dir_all(&db).unwrap();
+}
+
+#[test]
+fn verify_and_compact_db() {
+    let db = temp_db("verify_and_compact_db");
+    let storage = local();
+    let sort = vec!["sym".to_owned()];
+    for (syms, qty) in [(["c", "a"], [1i64, 2]), (["b", "a"], [3, 4])] {
+        let df = df!("sym" => syms, "qty" => qty).unwrap();
+        write_partition(
+            &storage,
+            &db,
+            "trade",
+            &date_scheme(),
+            Some(20240102),
+            &df,
+            &sort,
+            false,
+            false,
+        )
+        .unwrap();
+    }
+    let table = db.join("trade");
+    assert!(verify_db(&storage, &db).unwrap().is_empty());
+
+    // a crashed writer, an unknown file and a chunk no version refers to
+    fs::write(table.join(".20240102_0002.tmp"), b"partial").unwrap();
+    fs::write(table.join("notes"), b"").unwrap();
+    fs::copy(table.join("20240102_0001"), table.join("20240103_0000")).unwrap();
+    let issues = verify_db(&storage, &db)
+        .unwrap()
+        .iter()
+        .map(|issue| {
+            (
+                issue
+                    .path
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .to_string(),
+                issue.message.clone(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        issues,
+        vec![
+            (
+                ".20240102_0002.tmp".to_owned(),
+                "orphan temp file".to_owned()
+            ),
+            ("20240103_0000".to_owned(), "unreferenced chunk".to_owned()),
+            ("notes".to_owned(), "unknown file".to_owned()),
+        ]
+    );
+    fs::remove_file(table.join("notes")).unwrap();
+    fs::remove_file(table.join("20240103_0000")).unwrap();
+    fs::write(table.join(MANIFEST_DIR).join(".00000003.tmp"), b"partial").unwrap();
+
+    // chunks are merged by the recorded sort series into a new version, temp files are removed
+    let merged = compact_db(&storage, &db).unwrap();
+    assert_eq!(merged, vec![table.join("20240102_0002")]);
+    assert_eq!(
+        list_chunks(storage.as_ref(), &table, 20240102).unwrap(),
+        [2]
+    );
+    assert!(!table.join(".20240102_0002.tmp").exists());
+    assert!(!table.join(MANIFEST_DIR).join(".00000003.tmp").exists());
+    let df = read_file(storage.as_ref(), &merged[0]).unwrap();
+    assert_eq!(
+        df.column("sym")
+            .unwrap()
+            .str()
+            .unwrap()
+            .into_no_null_iter()
+            .collect::<Vec<_>>(),
+        ["a", "a", "b", "c"]
+    );
+    assert!(verify_db(&storage, &db).unwrap().is_empty());
+    assert!(compact_db(&storage, &db).unwrap().is_empty());
+    fs::remove_dir_all(&db).unwrap();
+}
+
+#[test]
+fn write_ipc_tables() {
+    let db = temp_db("write_ipc_tables");
+    let storage = local();
+    let scheme = date_scheme().with_format(TableFormat::IpcLz4);
+    let partition = Some(20240102);
+    let sort = vec!["sym".to_owned()];
+    let df1 = df!("sym" => ["b", "a"], "qty" => [1i64, 2]).unwrap();
+    let df2 = df!("sym" => ["c"], "qty" => [3i64]).unwrap();
+    write_partition(
+        &storage, &db, "trade", &scheme, partition, &df1, &sort, false, false,
+    )
+    .unwrap();
+    let path = write_partition(
+        &storage, &db, "trade", &scheme, partition, &df2, &sort, true, false,
+    )
+    .unwrap();
+
+    // merged chunks keep the table format
+    assert_eq!(path, db.join("trade").join("20240102_0002"));
+    assert_eq!(
+        list_chunks(storage.as_ref(), &db.join("trade"), 20240102).unwrap(),
+        [2]
+    );
+    assert_eq!(&fs::read(&path).unwrap()[..6], b"ARROW1");
+    assert_eq!(
+        read_file(storage.as_ref(), &path).unwrap(),
+        df!("sym" => ["a", "b", "c"], "qty" => [2i64, 1, 3]).unwrap()
+    );
+    assert!(write_partition(
+        &storage,
+        &db,
+        "trade",
+        &date_scheme(),
+        partition,
+        &df1,
+        &sort,
+        false,
+        false
+    )
+    .err()
+    .unwrap()
+    .to_string()
+    .contains("is stored as ipc_lz4, got parquet"));
+
+    let single = PartitionScheme::new(PartitionUnit::Single).with_format(TableFormat::Ipc);
+    write_partition(&storage, &db, "ref", &single, None, &df1, &[], false, false).unwrap();
+    let tables = load_db(&storage, &db).unwrap();
+    for (name, height) in [("ref", 2), ("trade", 3)] {
+        match &tables[name] {
+            J::Parted(parted) => {
+                assert_eq!(
+                    parted.scan(None).unwrap().collect().unwrap().height(),
+                    height
+                )
+            }
+            j => panic!("unexpected {:?}", j),
+        }
+    }
+    assert!(verify_db(&storage, &db).unwrap().is_empty());
+    fs::remove_dir_all(&db).unwrap();
+}
+
+#[test]
+fn memory_storage() {
+    let storage = MemoryStorage::default();
+    storage.write(Path::new("db/t/a"), b"a").unwrap();
+    storage.write(Path::new("db/t/b"), b"b").unwrap();
+    storage.write(Path::new("db/s"), b"s").unwrap();
+    assert_eq!(storage.list(Path::new("db")).unwrap(), ["s", "t"]);
+    assert!(storage.is_dir(Path::new("db/t")));
+    assert!(storage.is_file(Path::new("db/s")));
+    storage
+        .rename(Path::new("db/t/a"), Path::new("db/t/b"))
+        .unwrap();
+    assert_eq!(storage.read(Path::new("db/t/b")).unwrap(), b"a");
+    storage.delete(Path::new("db/t/b")).unwrap();
+    assert!(!storage.is_dir(Path::new("db/t")));
+    assert!(storage.read(Path::new("db/t/a")).is_err());
+    assert!(storage.write(Path::new("db"), b"").is_err());
+    assert!(storage.create_new(Path::new("db/l"), b"1").unwrap());
+    assert!(!storage.create_new(Path::new("db/l"), b"2").unwrap());
+    assert_eq!(storage.read(Path::new("db/l")).unwrap(), b"1");
+}
+
+#[test]
+fn write_memory_db() {
+    let db = temp_db("write_memory_db");
+    let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::default());
+    let sort = vec!["sym".to_owned()];
+    let df = df!("sym" => ["b", "a"], "qty" => [1i64, 2]).unwrap();
+    for partition in [20240102, 20240102, 20240103] {
+        write_partition(
+            &storage,
+            &db,
+            "trade",
+            &date_scheme(),
+            Some(partition),
+            &df,
+            &sort,
+            false,
+            false,
+        )
+        .unwrap();
+    }
+    write_partition(
+        &storage,
+        &db,
+        "ref",
+        &PartitionScheme::new(PartitionUnit::Single),
+        None,
+        &df,
+        &[],
+        false,
+        false,
+    )
+    .unwrap();
+    assert_eq!(
+        list_chunks(storage.as_ref(), &db.join("trade"), 20240102).unwrap(),
+        [0, 1]
+    );
+    assert!(verify_db(&storage, &db).unwrap().is_empty());
+    assert_eq!(
+        compact_db(&storage, &db).unwrap(),
+        [db.join("trade").join("20240102_0002")]
+    );
+
+    let tables = load_db(&storage, &db).unwrap();
+    for (name, height) in [("ref", 2), ("trade", 6)] {
+        match &tables[name] {
+            J::Parted(parted) => {
+                assert_eq!(
+                    parted.scan(None).unwrap().collect().unwrap().height(),
+                    height
+                )
+            }
+            j => panic!("unexpected {:?}", j),
+        }
+    }
+    // nothing is written to the local filesystem
+    assert!(!db.exists());
+    assert!(load_db(&local(), &db).is_err());
+}
+
+#[test]
+fn read_table_snapshots() {
+    let db = temp_db("read_table_snapshots");
+    let storage = local();
+    let table = db.join("trade");
+    let write = |qty: i64, overwrite: bool| {
+        let df = df!("sym" => ["a"], "qty" => [qty]).unwrap();
+        write_partition(
+            &storage,
+            &db,
+            "trade",
+            &date_scheme(),
+            Some(20240102),
+            &df,
+            &[],
+            false,
+            overwrite,
+        )
+        .unwrap()
+    };
+    write(1, false);
+    write(2, false);
+    let trade = match &load_db(&storage, &db).unwrap()["trade"] {
+        J::Parted(parted) => parted.clone(),
+        j => panic!("unexpected {:?}", j),
+    };
+    assert_eq!(list_versions(storage.as_ref(), &table).unwrap(), [1, 2]);
+
+    // an overwrite publishes a new version, the old one is still readable
+    write(3, true);
+    let qty = |parted: &Parted| {
+        parted.scan(None).unwrap().collect().unwrap()["qty"]
+            .i64()
+            .unwrap()
+            .into_no_null_iter()
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(qty(&trade), [3]);
+    let snapshot = load_snapshot(&storage, &table, 2).unwrap();
+    assert_eq!(snapshot.version, Some(2));
+    assert_eq!(qty(&snapshot), [1, 2]);
+    let df = snapshot
+        .scan_chunks(&[(20240102, 1)])
+        .unwrap()
+        .collect()
+        .unwrap();
+    assert_eq!(df["qty"].i64().unwrap().get(0), Some(2));
+    let df = snapshot.scan_chunks(&[]).unwrap().collect().unwrap();
+    assert_eq!(df.get_column_names(), ["sym", "qty"]);
+    assert_eq!(df.height(), 0);
+    assert!(verify_db(&storage, &db).unwrap().is_empty());
+
+    // versions and their chunks are retired after newer ones are published, once no snapshot
+    // pins them
+    drop(snapshot);
+    for qty in 4..4 + KEPT_VERSIONS as i64 {
+        write(qty, true);
+    }
+    assert_eq!(
+        list_versions(storage.as_ref(), &table).unwrap().len(),
+        KEPT_VERSIONS as usize
+    );
+    assert!(load_snapshot(&storage, &table, 2)
+        .err()
+        .unwrap()
+        .to_string()
+        .contains("is not kept"));
+    assert_eq!(
+        file_names(&table)
+            .iter()
+            .filter(|name| !name.starts_with('.'))
+            .count(),
+        KEPT_VERSIONS as usize
+    );
+    assert_eq!(qty(&trade), [3 + KEPT_VERSIONS as i64]);
+    fs::remove_dir_all(&db).unwrap();
+}
+
+#[test]
+fn lock_concurrent_writers() {
+    let db = temp_db("lock_concurrent_writers");
+    let storage = local();
+    let table = db.join("trade");
+    let lock = FileLock::acquire(storage.as_ref(), &table.join(LOCK_FILE)).unwrap();
+    assert!(
+        FileLock::try_acquire(storage.as_ref(), &table.join(LOCK_FILE))
+            .unwrap()
+            .is_none()
+    );
+    drop(lock);
+    assert!(!table.join(LOCK_FILE).exists());
+
+    // each writer publishes its own version, no chunk is lost or retired by another writer
+    let cat = DataType::Categorical(None, CategoricalOrdering::Physical);
+    std::thread::scope(|s| {
+        for i in 0..4i64 {
+            let (db, storage, cat) = (&db, &storage, &cat);
+            s.spawn(move || {
+                let mut df = df!("sym" => [format!("s{}", i)], "qty" => [i]).unwrap();
+                df.apply("sym", |s| s.cast(cat).unwrap()).unwrap();
+                write_partition(
+                    storage,
+                    db,
+                    "trade",
+                    &date_scheme(),
+                    Some(20240102),
+                    &df,
+                    &[],
+                    false,
+                    false,
+                )
+                .unwrap();
+            });
+        }
+    });
+    assert_eq!(
+        list_versions(storage.as_ref(), &table).unwrap(),
+        [1, 2, 3, 4]
+    );
+    assert_eq!(
+        list_chunks(storage.as_ref(), &table, 20240102)
+            .unwrap()
+            .len(),
+        4
+    );
+    assert_eq!(SymDict::latest(storage.as_ref(), &db).unwrap().len(), 4);
+    assert!(verify_db(&storage, &db).unwrap().is_empty());
+    let df = match &load_db(&storage, &db).unwrap()["trade"] {
+        J::Parted(parted) => parted.scan(None).unwrap().collect().unwrap(),
+        j => panic!("unexpected {:?}", j),
+    };
+    let mut qty: Vec<i64> = df["qty"].i64().unwrap().into_no_null_iter().collect();
+    qty.sort();
+    assert_eq!(qty, [0, 1, 2, 3]);
+    assert!(!table.join(LOCK_FILE).exists());
+    fs::remove_dir_all(&db).unwrap();
+}
+
+#[test]
+fn pin_versions_over_publishes() {
+    let db = temp_db("pin_versions_over_publishes");
+    let storage = local();
+    let table = db.join("trade");
+    let write = |qty: i64| {
+        let df = df!("sym" => ["a"], "qty" => [qty]).unwrap();
+        write_partition(
+            &storage,
+            &db,
+            "trade",
+            &date_scheme(),
+            Some(20240102),
+            &df,
+            &[],
+            false,
+            true,
+        )
+        .unwrap();
+    };
+    let meta_chunks = || {
+        read_meta(storage.as_ref(), &table).unwrap().unwrap()["chunk"]
+            .i64()
+            .unwrap()
+            .into_no_null_iter()
+            .collect::<Vec<_>>()
+    };
+    write(0);
+    let pinned = match &load_db(&storage, &db).unwrap()["trade"] {
+        J::Parted(parted) => parted.pin().unwrap(),
+        j => panic!("unexpected {:?}", j),
+    };
+    assert_eq!(pinned.version, Some(1));
+    let lf = pinned.scan(None).unwrap();
+
+    // more publishes than kept versions, the pinned version is retired but not its chunks
+    std::thread::scope(|s| {
+        for i in 0..4i64 {
+            let write = &write;
+            s.spawn(move || {
+                for j in 0..3 {
+                    write(1 + i * 3 + j);
+                }
+            });
+        }
+    });
+    let versions = list_versions(storage.as_ref(), &table).unwrap();
+    assert_eq!(versions.len(), KEPT_VERSIONS as usize);
+    assert_eq!(versions.last(), Some(&13));
+    assert_eq!(lf.collect().unwrap()["qty"].i64().unwrap().get(0), Some(0));
+    assert_eq!(
+        pinned.scan(None).unwrap().collect().unwrap()["qty"]
+            .i64()
+            .unwrap()
+            .get(0),
+        Some(0)
+    );
+    // metadata of chunks is kept with their files
+    assert_eq!(meta_chunks()[0], 0);
+    assert!(verify_db(&storage, &db).unwrap().is_empty());
+
+    // pins of crashed readers expire, temp files of failed publishes are found and removed
+    let dir = table.join(MANIFEST_DIR);
+    fs::write(dir.join("00000005_0_0.pin"), "0\n.sym\n20240102_0099\n").unwrap();
+    fs::write(dir.join(".00000099.tmp"), "partial").unwrap();
+    let issues = verify_db(&storage, &db).unwrap();
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].path, dir.join(".00000099.tmp"));
+    assert!(!dir.join("00000005_0_0.pin").exists());
+
+    // released pins are retired by the next publish
+    drop(pinned);
+    write(14);
+    assert!(!table.join("20240102_0000").exists());
+    assert!(!meta_chunks().contains(&0));
+    assert_eq!(meta_chunks().len(), KEPT_VERSIONS as usize);
+    assert!(file_names(&dir).iter().all(|name| !name.starts_with('.')));
+    assert_eq!(
+        file_names(&table)
+            .iter()
+            .filter(|name| !name.starts_with('.'))
+            .count(),
+        KEPT_VERSIONS as usize
+    );
+    assert!(verify_db(&storage, &db).unwrap().is_empty());
+    fs::remove_dir_all(&db).unwrap();
+}
@@ -549,3 +549,366 @@ fn parse_case07() {
         actual
     )
 }
+
+#[test]
+fn parse_case08() {
+    let code = "
+    from trade left join quote on {sym, date} inner join (from ref select {sym, sector}) on {sym};
+    from t1 union join t2 filter {qty > 0};
+    ";
+    let pairs = match JParser::parse(Rule::Program, code) {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("{}", e);
+            panic!("failed to parse")
+        }
+    };
+    let binding = pretty_format_rules(pairs);
+    let actual: Vec<&str> = binding.split("\n").collect();
+    assert_eq!(
+        vec![
+            "Exp -> SqlExp",
+            "   -> FromExp -> Id",
+            "   -> JoinExp",
+            "     -> JoinType",
+            "     -> Id",
+            "     -> JoinOn",
+            "       -> SeriesExp -> Id",
+            "       -> SeriesExp -> Id",
+            "   -> JoinExp",
+            "     -> JoinType",
+            "     -> BracketExp -> Exp -> SqlExp",
+            "           -> FromExp -> Id",
+            "           -> SelectOp",
+            "             -> SeriesExp -> Id",
+            "             -> SeriesExp -> Id",
+            "     -> JoinOn -> SeriesExp -> Id",
+            "Exp -> SqlExp",
+            "   -> FromExp -> Id",
+            "   -> JoinExp",
+            "     -> JoinType",
+            "     -> Id",
+            "   -> FilterExp -> BinarySqlExp",
+            "       -> Id",
+            "       -> BinaryOp",
+            "       -> Integer",
+            "EOI",
+            ""
+        ],
+        actual
+    )
+}
+
+#[test]
+fn parse_case09() {
+    let code = "
+    from trade filter {qty > 0} exec {price};
+    from trade exec distinct {sym, exchange};
+    from trade select distinct {} take 10;
+    ";
+    let pairs = match JParser::parse(Rule::Program, code) {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("{}", e);
+            panic!("failed to parse")
+        }
+    };
+    let binding = pretty_format_rules(pairs);
+    let actual: Vec<&str> = binding.split("\n").collect();
+    assert_eq!(
+        vec![
+            "Exp -> SqlExp",
+            "   -> FromExp -> Id",
+            "   -> FilterExp -> BinarySqlExp",
+            "       -> Id",
+            "       -> BinaryOp",
+            "       -> Integer",
+            "   -> ExecOp -> SeriesExp -> Id",
+            "Exp -> SqlExp",
+            "   -> FromExp -> Id",
+            "   -> ExecOp",
+            "     -> Distinct",
+            "     -> SeriesExp -> Id",
+            "     -> SeriesExp -> Id",
+            "Exp -> SqlExp",
+            "   -> FromExp -> Id",
+            "   -> SelectOp -> Distinct",
+            "   -> TakeOp -> Exp -> Integer",
+            "EOI",
+            ""
+        ],
+        actual
+    )
+}
+
+#[test]
+fn parse_case10() {
+    let code = "
+    from trade filter {date==2024-12-09} group {sym} select {qty = sum qty} filter {qty > 1000} sort {-qty};
+    ";
+    let pairs = match JParser::parse(Rule::Program, code) {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("{}", e);
+            panic!("failed to parse")
+        }
+    };
+    let binding = pretty_format_rules(pairs);
+    let actual: Vec<&str> = binding.split("\n").collect();
+    assert_eq!(
+        vec![
+            "Exp -> SqlExp",
+            "   -> FromExp -> Id",
+            "   -> FilterExp -> BinarySqlExp",
+            "       -> Id",
+            "       -> BinaryOp",
+            "       -> Date",
+            "   -> GroupExp -> SeriesExp -> Id",
+            "   -> SelectOp -> SeriesExp -> RenameSeriesExp",
+            "         -> SeriesName",
+            "         -> UnarySqlExp",
+            "           -> Id",
+            "           -> Id",
+            "   -> FilterExp -> BinarySqlExp",
+            "       -> Id",
+            "       -> BinaryOp",
+            "       -> Integer",
+            "   -> SortOp -> SortName",
+            "EOI",
+            ""
+        ],
+        actual
+    )
+}
+
+#[test]
+fn parse_case11() {
+    let code = "
+    explain from trade filter {date==2024-12-09} select {sym, qty}
+    ";
+    let pairs = match JParser::parse(Rule::Program, code) {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("{}", e);
+            panic!("failed to parse")
+        }
+    };
+    let binding = pretty_format_rules(pairs);
+    let actual: Vec<&str> = binding.split("\n").collect();
+    assert_eq!(
+        vec![
+            "Exp -> ExplainExp -> SqlExp",
+            "     -> FromExp -> Id",
+            "     -> FilterExp -> BinarySqlExp",
+            "         -> Id",
+            "         -> BinaryOp",
+            "         -> Date",
+            "     -> SelectOp",
+            "       -> SeriesExp -> Id",
+            "       -> SeriesExp -> Id",
+            "EOI",
+            ""
+        ],
+        actual
+    )
+}
+
+#[test]
+fn parse_case12() {
+    let code = "
+    from trade filter {c[col] > 0} select {c[!cols], max c[$`f64`], c[\"^price\"]}
+    ";
+    let pairs = match JParser::parse(Rule::Program, code) {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("{}", e);
+            panic!("failed to parse")
+        }
+    };
+    let binding = pretty_format_rules(pairs);
+    let actual: Vec<&str> = binding.split("\n").collect();
+    assert_eq!(
+        vec![
+            "Exp -> SqlExp",
+            "   -> FromExp -> Id",
+            "   -> FilterExp -> BinarySqlExp",
+            "       -> Columns -> Exp -> Id",
+            "       -> BinaryOp",
+            "       -> Integer",
+            "   -> SelectOp",
+            "     -> SeriesExp -> Columns",
+            "         -> ColumnsExclude",
+            "         -> Exp -> Id",
+            "     -> SeriesExp -> UnarySqlExp",
+            "         -> Id",
+            "         -> Columns",
+            "           -> ColumnsByType",
+            "           -> Exp -> Cat",
+            "     -> SeriesExp -> Columns -> Exp -> String",
+            "EOI",
+            ""
+        ],
+        actual
+    )
+}
+
+#[test]
+fn parse_case13() {
+    let code = "
+    from ungroup(from trade group {sym} select {qty}) filter {qty > 0}
+    ";
+    let pairs = match JParser::parse(Rule::Program, code) {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("{}", e);
+            panic!("failed to parse")
+        }
+    };
+    let binding = pretty_format_rules(pairs);
+    let actual: Vec<&str> = binding.split("\n").collect();
+    assert_eq!(
+        vec![
+            "Exp -> SqlExp",
+            "   -> FromExp -> FnCall",
+            "       -> Id",
+            "       -> Arg -> Exp -> SqlExp",
+            "             -> FromExp -> Id",
+            "             -> GroupExp -> SeriesExp -> Id",
+            "             -> SelectOp -> SeriesExp -> Id",
+            "   -> FilterExp -> BinarySqlExp",
+            "       -> Id",
+            "       -> BinaryOp",
+            "       -> Integer",
+            "EOI",
+            ""
+        ],
+        actual
+    )
+}
+
+#[test]
+fn parse_case14() {
+    let code = "
+    with {a = from trade filter {qty > 0}, b = from a group {sym} select {qty = sum qty}} from a left join b on {sym}
+    ";
+    let pairs = match JParser::parse(Rule::Program, code) {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("{}", e);
+            panic!("failed to parse")
+        }
+    };
+    let binding = pretty_format_rules(pairs);
+    let actual: Vec<&str> = binding.split("\n").collect();
+    assert_eq!(
+        vec![
+            "Exp -> SqlExp",
+            "   -> WithExp",
+            "     -> WithBinding",
+            "       -> Id",
+            "       -> SqlExp",
+            "         -> FromExp -> Id",
+            "         -> FilterExp -> BinarySqlExp",
+            "             -> Id",
+            "             -> BinaryOp",
+            "             -> Integer",
+            "     -> WithBinding",
+            "       -> Id",
+            "       -> SqlExp",
+            "         -> FromExp -> Id",
+            "         -> GroupExp -> SeriesExp -> Id",
+            "         -> SelectOp -> SeriesExp -> RenameSeriesExp",
+            "               -> SeriesName",
+            "               -> UnarySqlExp",
+            "                 -> Id",
+            "                 -> Id",
+            "   -> FromExp -> Id",
+            "   -> JoinExp",
+            "     -> JoinType",
+            "     -> Id",
+            "     -> JoinOn -> SeriesExp -> Id",
+            "EOI",
+            ""
+        ],
+        actual
+    )
+}
+
+#[test]
+fn parse_case15() {
+    let code = r#"
+    h0 = h[`:localhost:5001`];
+    count h[h0]("from t select {qty}");
+    h[h0](`f`, 1)
+    "#;
+    let pairs = match JParser::parse(Rule::Program, code) {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("{}", e);
+            panic!("failed to parse")
+        }
+    };
+    let binding = pretty_format_rules(pairs);
+    let actual: Vec<&str> = binding.split("\n").collect();
+    assert_eq!(
+        vec![
+            "Exp -> AssignmentExp",
+            "   -> Id",
+            "   -> Exp -> Handle -> Exp -> Cat",
+            "Exp -> UnaryExp",
+            "   -> Id",
+            "   -> FnCall",
+            "     -> Handle -> Exp -> Id",
+            "     -> Arg -> Exp -> String",
+            "Exp -> FnCall",
+            "   -> Handle -> Exp -> Id",
+            "   -> Arg -> Exp -> Cat",
+            "   -> Arg -> Exp -> Integer",
+            "EOI",
+            ""
+        ],
+        actual
+    )
+}
+
+#[test]
+fn parse_case16() {
+    let code = "
+    from trade group {sym} select {qty} ungroup filter {qty > 1};
+    from trade pivot(`sym`, `date`, `qty`, `sum`) sort {date}
+    ";
+    let pairs = match JParser::parse(Rule::Program, code) {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("{}", e);
+            panic!("failed to parse")
+        }
+    };
+    let binding = pretty_format_rules(pairs);
+    let actual: Vec<&str> = binding.split("\n").collect();
+    assert_eq!(
+        vec![
+            "Exp -> SqlExp",
+            "   -> FromExp -> Id",
+            "   -> GroupExp -> SeriesExp -> Id",
+            "   -> SelectOp -> SeriesExp -> Id",
+            "   -> ReshapeOp -> ReshapeName",
+            "   -> FilterExp -> BinarySqlExp",
+            "       -> Id",
+            "       -> BinaryOp",
+            "       -> Integer",
+            "Exp -> SqlExp",
+            "   -> FromExp -> Id",
+            "   -> ReshapeOp",
+            "     -> ReshapeName",
+            "     -> Arg -> Exp -> Cat",
+            "     -> Arg -> Exp -> Cat",
+            "     -> Arg -> Exp -> Cat",
+            "     -> Arg -> Exp -> Cat",
+            "   -> SortOp -> SortName",
+            "EOI",
+            ""
+        ],
+        actual
+    )
+}
@@ -0,0 +1,130 @@
+use indexmap::IndexMap;
+use jasmine::j::J;
+use jasmine::serialize::MAX_DEPTH;
+use ndarray::array;
+use polars::df;
+use polars::prelude::{CategoricalOrdering, DataType, NamedFrom, TimeUnit};
+use polars::series::Series;
+
+fn round_trip(j: &J) -> J {
+    J::deserialize(&j.serialize().unwrap()).unwrap()
+}
+
+#[test]
+fn serialize_scalars() {
+    for j in [
+        J::Boolean(true),
+        J::I64(-7),
+        J::Date(19724),
+        J::Time(3_600_000_000_000),
+        J::Datetime {
+            ms: 1_704_153_600_000,
+            timezone: "Asia/Tokyo".to_owned(),
+        },
+        J::Timestamp {
+            ns: 1_704_153_600_000_000_001,
+            timezone: "".to_owned(),
+        },
+        J::Duration(-1),
+        J::F64(1.5),
+        J::String("string❤️".to_owned()),
+        J::Cat("a".to_owned()),
+        J::None,
+        J::Err("type".to_owned()),
+    ] {
+        assert_eq!(round_trip(&j), j);
+    }
+    assert_eq!(
+        J::I64(1).serialize().unwrap(),
+        [0xfb, 1, 0, 0, 0, 0, 0, 0, 0]
+    );
+    assert_eq!(
+        J::Err("".to_owned()).serialize().unwrap(),
+        [128, 0, 0, 0, 0]
+    );
+}
+
+#[test]
+fn serialize_collections() {
+    let ts = Series::new("ts".into(), [1i64, 2])
+        .cast(&DataType::Datetime(
+            TimeUnit::Nanoseconds,
+            Some("UTC".into()),
+        ))
+        .unwrap();
+    let series = J::Series(ts);
+    let bytes = series.serialize().unwrap();
+    assert_eq!(bytes[0], 9);
+    assert_eq!(J::deserialize(&bytes).unwrap(), series);
+
+    let cat = DataType::Categorical(None, CategoricalOrdering::Physical);
+    let s = Series::new("sym".into(), ["b", "a", "b"])
+        .cast(&cat)
+        .unwrap();
+    let J::Series(s) = round_trip(&J::Series(s)) else {
+        panic!("expect series");
+    };
+    assert!(matches!(s.dtype(), DataType::Categorical(_, _)));
+    assert_eq!(
+        s.cast(&DataType::String).unwrap(),
+        Series::new("sym".into(), ["b", "a", "b"])
+    );
+
+    let df = df!("sym" => ["a", "b"], "qty" => [1i64, 2]).unwrap();
+    for j in [
+        J::Matrix(array![[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]].into_shared()),
+        J::DataFrame(df.clone()),
+        J::MixedList(vec![J::I64(1), J::DataFrame(df), J::None]),
+        J::Dict(IndexMap::from([
+            ("b".to_owned(), J::String("b".to_owned())),
+            ("a".to_owned(), J::MixedList(vec![])),
+        ])),
+    ] {
+        assert_eq!(round_trip(&j), j);
+    }
+}
+
+#[test]
+fn deserialize_invalid_bytes() {
+    let bytes = J::String("abc".to_owned()).serialize().unwrap();
+    for (bytes, err) in [
+        (&bytes[..4], "expect 4 bytes at 1, got 3"),
+        (&[200u8][..], "unknown type code -56"),
+        (&[0u8, 0][..], "1 trailing bytes"),
+        (
+            &[90u8, 255, 255, 255, 255][..],
+            "expect 1 bytes at 5, got 0",
+        ),
+    ] {
+        assert!(J::deserialize(bytes)
+            .err()
+            .unwrap()
+            .to_string()
+            .contains(err));
+    }
+    assert!(J::Series(Series::new_null("".into(), 2))
+        .serialize()
+        .is_err());
+}
+
+#[test]
+fn deserialize_nested_depth() {
+    let mut j = J::None;
+    for _ in 0..MAX_DEPTH {
+        j = J::MixedList(vec![j]);
+    }
+    assert_eq!(round_trip(&j), j);
+    let j = J::Dict(IndexMap::from([("a".to_owned(), j)]));
+    assert!(J::deserialize(&j.serialize().unwrap())
+        .err()
+        .unwrap()
+        .to_string()
+        .contains("exceeds max depth 64"));
+    // a crafted payload of nested lists fails before recursing deeply
+    let bytes = [90u8, 1, 0, 0, 0].repeat(1_000_000);
+    assert!(J::deserialize(&bytes)
+        .err()
+        .unwrap()
+        .to_string()
+        .contains("exceeds max depth 64"));
+}
@@ -7,12 +7,16 @@ with contextlib.suppress(ImportError):
         AstAssign,
         AstBinOp,
         AstCall,
+        AstColumns,
         AstDataFrame,
         AstDict,
+        AstExplain,
         AstFn,
+        AstHandle,
         AstId,
         AstIf,
         AstIndexAssign,
+        AstJoin,
         AstList,
         AstMatrix,
         AstOp,
@@ -26,8 +30,21 @@ with contextlib.suppress(ImportError):
         AstUnaryOp,
         AstWhile,
         JObj,
+        compact_db,
+        compact_syms,
+        deserialize,
+        hash_bucket,
+        list_versions,
+        load_db,
         parse_source_code,
+        pin_table,
         print_trace,
+        read_pin,
+        read_syms,
+        scan_table,
+        serialize,
+        verify_db,
+        write_partition,
     )
 
 
@@ -54,6 +71,10 @@ class AstType(Enum):
     Sql = 19
     SqlBracket = 20
     Skip = 21
+    Join = 22
+    Explain = 23
+    Columns = 24
+    Handle = 25
 
 
 def downcast_ast_node(node: Ast):
@@ -103,6 +124,14 @@ def downcast_ast_node(node: Ast):
             return node.sql_bracket()
         case AstType.Skip:
             return node.skip()
+        case AstType.Join:
+            return node.join()
+        case AstType.Explain:
+            return node.explain()
+        case AstType.Columns:
+            return node.columns()
+        case AstType.Handle:
+            return node.handle()
 
 
 all = [
@@ -110,12 +139,16 @@ all = [
     AstAssign,
     AstBinOp,
     AstCall,
+    AstColumns,
     AstDataFrame,
     AstDict,
+    AstExplain,
     AstFn,
+    AstHandle,
     AstId,
     AstIf,
     AstIndexAssign,
+    AstJoin,
     AstList,
     AstMatrix,
     AstOp,
@@ -129,6 +162,19 @@ all = [
     AstUnaryOp,
     AstWhile,
     JObj,
+    compact_db,
+    compact_syms,
+    deserialize,
+    hash_bucket,
+    list_versions,
+    load_db,
     parse_source_code,
+    pin_table,
     print_trace,
+    read_pin,
+    read_syms,
+    scan_table,
+    serialize,
+    verify_db,
+    write_partition,
 ]
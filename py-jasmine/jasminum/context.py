@@ -1,7 +1,10 @@
 class Context:
     locals: dict[str, any]
     handles: dict[int, any]
+    # named intermediate lazy frames of sql 'with'
+    frames: dict[str, any]
 
     def __init__(self, locals: dict) -> None:
         self.locals = locals
         self.handles = dict()
+        self.frames = dict()
@@ -1,38 +1,73 @@
+import threading
 import zoneinfo
 from pathlib import Path
 from typing import Callable
 
 import polars as pl
 
-from .ast import print_trace
+from .ast import load_db, print_trace
 from .exceptions import JasmineEvalException
 from .expr import selector
-from .io import wpart
-from .j import J, JParted, JType
+from .io import compactdb, compactsym, snapshot, verifydb, versions, wpart
+from .j import J, JType
 from .j_fn import JFn
-from .operator import add, cast, rand
+from .join import aj, wj
+from .operator import add, cast, rand, xbar
+from .predicate import between, is_in, like, match
+from .reshape import pivot, ungroup, unpivot
 from .temporal import tz
 
 
 class Engine:
     globals: dict[str, any]
+    # open connections by handles numbered from 1, handles are not reused
     handles: dict[int, any]
+    last_handle: int
     sources: dict[int, (str, str)]
     builtins: dict[str, any]
+    # held while evaluating, the console and remote messages share one engine
+    lock: threading.Lock
 
     def __init__(self) -> None:
         self.globals = dict()
         self.handles = dict()
+        self.last_handle = 0
         self.sources = dict()
         self.builtins = dict()
+        self.lock = threading.Lock()
+
+        # share categorical encoding across dataframes, required to join on 'cat' series
+        pl.enable_string_cache()
 
         self.register_builtin("+", add)
         self.register_builtin("?", rand)
         self.register_builtin("$", cast)
         self.register_builtin("tz", tz)
+        self.register_builtin("xbar", xbar)
+        self.register_builtin("in", is_in)
+        self.register_builtin("between", between)
+        self.register_builtin("like", like)
+        self.register_builtin("match", match)
         self.register_builtin("selector", selector)
         self.register_builtin("load", lambda x: self.load_partitioned_df(x))
         self.register_builtin("wpart", wpart)
+        self.register_builtin("compactsym", compactsym)
+        self.register_builtin("verifydb", verifydb)
+        self.register_builtin("compactdb", compactdb)
+        self.register_builtin("snapshot", snapshot)
+        self.register_builtin("versions", versions)
+        self.register_builtin("aj", aj)
+        self.register_builtin("wj", wj)
+        self.register_builtin("pivot", pivot)
+        self.register_builtin("unpivot", unpivot)
+        self.register_builtin("ungroup", ungroup)
+        self.register_builtin("hclose", lambda handle: self.close_handle(handle))
+        self.register_builtin(
+            "query",
+            lambda table, filters, groups, selects, *, source_id, start: self.query(
+                table, filters, groups, selects, source_id, start
+            ),
+        )
         self.builtins["timezone"] = J(
             pl.Series("timezone", sorted(list(zoneinfo.available_timezones())))
         )
@@ -46,40 +81,38 @@ class Engine:
             arg_num,
         )
 
+    def query(
+        self, table: J, filters: J, groups: J, selects: J, source_id: int, start: int
+    ) -> J:
+        # eval depends on engine
+        from .eval import eval_query
+
+        return eval_query(table, filters, groups, selects, self, source_id, start)
+
     def get_trace(self, source_id: int, pos: int, msg: str) -> str:
         source, path = self.sources.get(source_id)
         return print_trace(source, path, pos, msg)
 
-    # YYYYMMDD_00
-    # YYYY_00
+    def open_handle(self, sock: any) -> int:
+        self.last_handle += 1
+        self.handles[self.last_handle] = sock
+        return self.last_handle
+
+    def close_handle(self, handle: J) -> J:
+        if handle.j_type != JType.INT or handle.data not in self.handles:
+            raise JasmineEvalException("'hclose' requires an open handle")
+        self.handles.pop(handle.data).close()
+        return J(None, JType.NONE)
+
+    # <yyyymmdd>_<nnnn>
+    # <yyyy>_<nnnn>
     def load_partitioned_df(self, path: J) -> J:
         if path.j_type != JType.CAT and path.j_type != JType.STRING:
             raise JasmineEvalException(
                 "'load' requires cat|string, got %s" % path.j_type
             )
-        p = Path(path.data).resolve()
         frames = []
-        for df_path in p.iterdir():
-            # skip name starts with digit
-            if df_path.name[0].isdigit():
-                continue
-            else:
-                if df_path.is_file():
-                    self.globals[df_path.name] = J(JParted(df_path, 0, []))
-                    frames.append(df_path.name)
-                else:
-                    partitions = []
-                    unit = 0
-                    for partition in df_path.iterdir():
-                        if unit == 0:
-                            if len(partition.name) <= 8:
-                                unit = 4
-                            else:
-                                unit = 8
-                        partitions.append(int(partition.name[:unit]))
-                    if len(partitions) > 0:
-                        self.globals[df_path.name] = J(
-                            JParted(df_path, unit, sorted(partitions))
-                        )
-                        frames.append(df_path.name)
+        for name, parted in load_db(str(Path(path.data).resolve())):
+            self.globals[name] = J(parted)
+            frames.append(name)
         return J(pl.Series("", frames))
@@ -1,3 +1,5 @@
+import inspect
+import warnings
 from copy import copy
 from typing import Callable
 
@@ -8,12 +10,16 @@ from .ast import (
     AstAssign,
     AstBinOp,
     AstCall,
+    AstColumns,
     AstDataFrame,
     AstDict,
+    AstExplain,
     AstFn,
+    AstHandle,
     AstId,
     AstIf,
     AstIndexAssign,
+    AstJoin,
     AstList,
     AstMatrix,
     AstOp,
@@ -34,10 +40,13 @@ from .ast import (
 from .context import Context
 from .engine import Engine
 from .exceptions import JasmineEvalException
-from .j import J, JType
+from .expr import columns
+from .j import J, JParted, JType
 from .j_fn import JFn
-from .operator import cast
-from .util import date_to_num
+from .meta import SKIP_OP, plan_chunks
+from .operator import cast, xbar
+from .predicate import between, is_in, like, match
+from .prune import FLIPPED_OP, PRUNE_OP, is_exact, prune
 
 
 def import_path(path: str, engine: Engine):
@@ -105,6 +114,8 @@ def eval_node(node, engine: Engine, ctx: Context, is_in_fn=False) -> J:
         fn_args = []
         for arg in node.args:
             fn_args.append(eval_node(arg, engine, ctx, is_in_fn))
+        if isinstance(f, AstHandle):
+            return eval_remote(fn, engine, node.source_id, node.start, *fn_args)
         return eval_fn(fn, engine, ctx, node.source_id, node.start, *fn_args)
     elif isinstance(node, AstOp):
         if node.name in engine.builtins:
@@ -125,6 +136,21 @@ def eval_node(node, engine: Engine, ctx: Context, is_in_fn=False) -> J:
             series = eval_node(series, engine, ctx, is_in_fn).to_series()
             df.append(series)
         return J(pl.DataFrame(df))
+    elif isinstance(node, AstList):
+        items = []
+        for item in node.exps:
+            item = downcast_ast_node(item)
+            # operators are kept as names for functional query
+            if isinstance(item, AstOp):
+                items.append(J(item.name, JType.CAT))
+            else:
+                items.append(eval_node(item, engine, ctx, is_in_fn))
+        return J(items, JType.LIST)
+    elif isinstance(node, AstDict):
+        d = {}
+        for key, value in zip(node.keys, node.values):
+            d[key] = eval_node(value, engine, ctx, is_in_fn)
+        return J(d, JType.DICT)
     elif isinstance(node, AstSeries):
         j = eval_node(node.exp, engine, ctx, is_in_fn)
         series = j.to_series()
@@ -132,6 +158,15 @@ def eval_node(node, engine: Engine, ctx: Context, is_in_fn=False) -> J:
         return J(series)
     elif isinstance(node, AstSql):
         return eval_sql(node, engine, ctx, node.source_id, node.start, is_in_fn)
+    elif isinstance(node, AstColumns):
+        j = eval_node(node.exp, engine, ctx, is_in_fn)
+        return J(columns(j, node.exclude, node.by_type))
+    elif isinstance(node, AstExplain):
+        sql = downcast_ast_node(node.exp)
+        return eval_explain(sql, engine, ctx, is_in_fn)
+    elif isinstance(node, AstHandle):
+        j = eval_node(node.exp, engine, ctx, is_in_fn)
+        return eval_handle(j, engine, node.source_id, node.start)
     else:
         raise JasmineEvalException("not yet implemented - %s" % node)
 
@@ -157,6 +192,9 @@ def eval_fn(fn: JFn, engine: Engine, ctx: Context, source_id: int, start: int, *
 
         if missing_arg_num == 0 and fn.arg_num == len(args):
             if isinstance(fn.fn, Callable):
+                # built-in fns with keyword-only arguments take the position of the call
+                if fn.fn.__code__.co_kwonlyargcount > 0:
+                    return fn.fn(**fn_args, source_id=source_id, start=start)
                 return fn.fn(**fn_args)
             else:
                 return eval_node(fn.fn, engine, Context(fn_args), True)
@@ -170,11 +208,52 @@ def eval_fn(fn: JFn, engine: Engine, ctx: Context, source_id: int, start: int, *
         raise JasmineEvalException(engine.get_trace(source_id, start, str(e)))
 
 
-def is_between(expr: pl.Expr, bound: J | list[pl.Expr]):
-    if isinstance(bound, list):
-        return pl.Expr.is_between(expr, *bound)
-    elif bound.j_type == JType.SERIES and bound.data.count() == 2:
-        return pl.Expr.is_between(expr, pl.lit(bound.data[0]), pl.lit(bound.data[1]))
+# an address opens a connection, an int refers to an open one
+def eval_handle(j: J, engine: Engine, source_id: int, start: int) -> J:
+    # server depends on eval
+    from .server import connect
+
+    try:
+        if j.j_type == JType.INT:
+            if j.data not in engine.handles:
+                raise JasmineEvalException("handle %s is not open" % j.data)
+            return j
+        elif j.j_type == JType.CAT or j.j_type == JType.STRING:
+            return J(engine.open_handle(connect(j.data)), JType.INT)
+        else:
+            raise JasmineEvalException(
+                "'h' requires int|cat|string, got %s" % j.j_type
+            )
+    except Exception as e:
+        raise JasmineEvalException(engine.get_trace(source_id, start, str(e)))
+
+
+# a string is evaluated as source code remotely, otherwise a function name followed by
+# its arguments, local fns are not sent as the remote process can't evaluate them
+def eval_remote(handle: J, engine: Engine, source_id: int, start: int, *args) -> J:
+    from .server import call
+
+    try:
+        fn = args[0] if isinstance(args[0], J) else J(args[0])
+        if fn.j_type == JType.STRING and len(args) == 1:
+            j = fn
+        elif fn.j_type == JType.CAT:
+            j = J(list(args), JType.LIST)
+        else:
+            raise JasmineEvalException(
+                "remote call requires source code as string, or a fn name as cat "
+                "followed by its arguments, got %s" % fn.j_type.name
+            )
+        try:
+            res = call(engine.handles[handle.data], j)
+        except OSError:
+            engine.handles.pop(handle.data).close()
+            raise
+        if res.j_type == JType.ERR:
+            raise JasmineEvalException(res.data)
+        return res
+    except Exception as e:
+        raise JasmineEvalException(engine.get_trace(source_id, start, str(e)))
 
 
 SQL_FN = {
@@ -289,7 +368,7 @@ SQL_FN = {
     "var0": lambda x: pl.Expr.var(x, 0),
     "var1": lambda x: pl.Expr.var(x, 1),
     # binary
-    "between": is_between,
+    "between": between,
     # bottom k
     "bottom": lambda x, y: pl.Expr.bottom_k(y, x),
     "corr0": lambda x, y: pl.corr(x, y, ddof=0),
@@ -303,11 +382,11 @@ SQL_FN = {
     "evar": lambda x, y: pl.Expr.ewm_mean(y, alpha=x),
     # fill null
     "fill": lambda x, y: pl.Expr.fill_null(y, x),
-    "in": pl.Expr.is_in,
+    "in": is_in,
     "intersect": 2,
-    "like": 2,
+    "like": like,
     "log": lambda x, y: pl.Expr.log(x, y),
-    "matches": 2,
+    "match": match,
     "join": 2,
     # rolling functions
     "rmax": 2,
@@ -335,6 +414,7 @@ SQL_FN = {
     "union": 2,
     "wmean": lambda x, y: pl.Expr.dot(x, y) / pl.Expr.sum(x),
     "wsum": pl.Expr.dot,
+    "xbar": xbar,
     "over": pl.Expr.over,
     # other functions
     "clip": 3,
@@ -346,10 +426,14 @@ SQL_FN = {
 
 
 # op: String,
+# distinct: bool,
 # from: Ast,
+# joins: Vec<Ast>,
 # filters: Vec<Ast>,
 # groups: Vec<Ast>,
 # ops: Vec<Ast>,
+# reshapes: Vec<Ast>,
+# post_filters: Vec<Ast>,
 # sorts: Vec<Ast>,
 # take: Ast,
 def eval_sql(
@@ -360,87 +444,77 @@ def eval_sql(
     start: int,
     is_in_fn: bool,
 ):
+    df, _ = eval_sql_plan(sql, engine, ctx, source_id, start, is_in_fn)
+    df = df.collect()
+    if sql.op == "exec":
+        if df.width == 1:
+            return J(df.to_series())
+        else:
+            d = {}
+            for series in df.get_columns():
+                d[series.name] = J(series)
+            return J(d, JType.DICT)
+    else:
+        return J(df)
+
+
+# returns the optimized logical plan, the chunk files scanned and the projected columns
+def eval_explain(sql: AstSql, engine: Engine, ctx: Context, is_in_fn: bool) -> J:
+    df, chunks = eval_sql_plan(sql, engine, ctx, sql.source_id, sql.start, is_in_fn)
+    return J(
+        {
+            "plan": J(df.explain(optimized=True), JType.STRING),
+            "chunks": J(pl.Series("chunks", [str(c) for c in chunks], pl.String)),
+            "columns": J(
+                pl.Series("columns", df.collect_schema().names(), pl.String)
+            ),
+        },
+        JType.DICT,
+    )
+
+
+def eval_sql_plan(
+    sql: AstSql,
+    engine: Engine,
+    ctx: Context,
+    source_id: int,
+    start: int,
+    is_in_fn: bool,
+) -> tuple[pl.LazyFrame, list[str]]:
+    frames = ctx.frames
     try:
-        j = eval_node(sql.from_df, engine, ctx, is_in_fn)
-        if j.j_type == JType.DATAFRAME:
+        with_chunks = []
+        if len(sql.withs) > 0:
+            ctx.frames = dict(frames)
+            for node in sql.withs:
+                binding = downcast_ast_node(node)
+                with_sql = downcast_ast_node(binding.exp)
+                ctx.frames[binding.id], chunks = eval_sql_plan(
+                    with_sql, engine, ctx, with_sql.source_id, with_sql.start, is_in_fn
+                )
+                with_chunks += chunks
+
+        j = eval_sql_frame(sql.from_df, engine, ctx, is_in_fn)
+        filters = sql.filters
+        chunks = []
+        if isinstance(j, pl.LazyFrame):
+            df = j
+        elif j.j_type == JType.DATAFRAME:
             df = j.data.lazy()
-            if len(sql.filters) > 0:
-                for node in sql.filters:
-                    df = df.filter(eval_sql_op(node, engine, ctx, is_in_fn))
         elif j.j_type == JType.PARTED:
-            missing_part_err = JasmineEvalException(
-                "dataframe partitioned by %s requires its partitioned unit condition('==', 'in' or 'between') as its first filter clause"
-                % j.j_type.name
+            parted = j.data.pin()
+            nums, filters, conditions = eval_parted_filters(
+                parted, filters, engine, ctx, is_in_fn
             )
-            # partitioned table
-            if len(sql.filters) > 0:
-                first_filter = downcast_ast_node(sql.filters[0])
-                if isinstance(first_filter, AstBinOp):
-                    op = downcast_ast_node(first_filter.op)
-                    lhs = downcast_ast_node(first_filter.lhs)
-                    rhs = eval_node(first_filter.rhs, engine, ctx, is_in_fn)
-                    if not (isinstance(lhs, AstId) and lhs.name == j.data.get_unit()):
-                        raise missing_part_err
-                    if op.name == "==" and rhs.j_type == JType.DATE:
-                        date_num = rhs.date_num()
-                        partitions = j.data.get_partition_paths(date_num, date_num)
-                        if len(partitions) == 0:
-                            partitions = [j.data.get_latest_path()]
-                            df = pl.scan_parquet(partitions, n_rows=0)
-                        else:
-                            df = pl.scan_parquet(partitions)
-                    elif (
-                        op.name == "between"
-                        and rhs.j_type == JType.SERIES
-                        and rhs.data.dtype == pl.Date
-                        and rhs.data.count() == 2
-                        and rhs.data.null_count() == 0
-                    ):
-                        start_date = rhs.data[0]
-                        end_date = rhs.data[1]
-                        partitions = j.data.get_partition_paths(
-                            date_to_num(start_date), date_to_num(end_date)
-                        )
-                        if len(partitions) == 0:
-                            partitions = [j.data.get_latest_path()]
-                            df = pl.scan_parquet(partitions, n_rows=0)
-                        else:
-                            df = pl.scan_parquet(partitions)
-                    elif op.name == "in" and rhs.j_type == JType.DATE:
-                        date_num = date_to_num(rhs.data)
-                        if date_num in j.data.partitions:
-                            df = pl.scan_parquet(
-                                j.data.get_partition_paths_by_date_nums([date_num])
-                            )
-                        else:
-                            df = pl.scan_parquet([j.data.get_latest_path()], n_rows=0)
-                    elif op.name == "in" and (
-                        rhs.j_type == JType.SERIES and rhs.data.dtype == pl.Date
-                    ):
-                        date_nums = []
-                        for date in rhs.data:
-                            if date:
-                                date_num = date_to_num(date)
-                                if date_num in j.data.partitions:
-                                    date_nums.append(date_num)
-                        partitions = j.data.get_partition_paths_by_date_nums(date_nums)
-                        if len(partitions) > 0:
-                            df = pl.scan_parquet(partitions)
-                        else:
-                            df = pl.scan_parquet([j.data.get_latest_path()], n_rows=0)
-                    else:
-                        raise missing_part_err
-                else:
-                    raise missing_part_err
-            else:
-                raise missing_part_err
-
-            if len(sql.filters) > 1:
-                for node in sql.filters[1:]:
-                    df = df.filter(eval_sql_op(node, engine, ctx, is_in_fn))
+            df, chunks = scan_partitions(parted, nums, conditions)
         else:
             raise JasmineEvalException("'from' requires dataframe, got %s" % j.j_type)
 
+        for node in sql.joins:
+            df = eval_sql_join(downcast_ast_node(node), df, engine, ctx, is_in_fn)
+
+        filters = [eval_sql_op(node, engine, ctx, is_in_fn) for node in filters]
+
         groups = []
         if len(sql.groups) > 0:
             for node in sql.groups:
@@ -454,32 +528,31 @@ def eval_sql(
                     expr = expr.to_expr()
                 ops.append(expr)
 
-        if len(groups) > 0:
-            if sql.op == "select":
-                if len(ops) == 0:
-                    df = df.group_by(groups, maintain_order=True).agg(
-                        pl.col("*").last()
-                    )
-                else:
-                    df = df.group_by(groups, maintain_order=True).agg(ops)
-            elif sql.op == "update":
-                over_ops = []
-                for op in ops:
-                    over_ops.append(op.over(groups))
-                df.with_columns(over_ops)
-            else:
+        df = eval_sql_query(df, sql.op, filters, groups, ops, engine, source_id, start)
+
+        if sql.distinct:
+            df = df.unique(maintain_order=True)
+
+        # pivot, unpivot and ungroup take the result as their dataframe
+        for node in sql.reshapes:
+            call = downcast_ast_node(node)
+            fn = eval_node(call.f, engine, ctx, is_in_fn)
+            args = [eval_node(arg, engine, ctx, is_in_fn) for arg in call.args]
+            j = eval_fn(
+                fn, engine, ctx, call.source_id, call.start, J(df.collect()), *args
+            )
+            if not isinstance(j, J):
                 raise JasmineEvalException(
                     engine.get_trace(
-                        source_id, start, "not support 'delete' with 'group'"
+                        call.source_id,
+                        call.start,
+                        "requires all arguments other than the dataframe",
                     )
                 )
-        elif len(ops) > 0:
-            if sql.op == "select":
-                df = df.select(ops)
-            elif sql.op == "update":
-                df = df.with_columns(ops)
-            else:
-                df.drop(ops)
+            df = j.data.lazy()
+
+        for node in sql.post_filters:
+            df = df.filter(eval_sql_op(node, engine, ctx, is_in_fn))
 
         sorts = []
         descendings = []
@@ -510,10 +583,304 @@ def eval_sql(
                 )
             )
 
-        return J(df.collect())
+        return df, with_chunks + chunks
     except Exception as e:
         raise e
         # raise JasmineEvalException(engine.get_trace(source_id, start, str(e)))
+    finally:
+        ctx.frames = frames
+
+
+# named intermediate frames of 'with' are referred as lazy frames
+def eval_sql_frame(
+    node, engine: Engine, ctx: Context, is_in_fn: bool
+) -> J | pl.LazyFrame:
+    if isinstance(node, Ast):
+        node = downcast_ast_node(node)
+    if isinstance(node, AstId) and node.name in ctx.frames:
+        return ctx.frames[node.name]
+    return eval_node(node, engine, ctx, is_in_fn)
+
+
+# conditions on the partitioned unit are consumed to prune partitions, others comparing
+# a series with a value are kept as filters and used to skip chunks
+def eval_parted_filters(
+    parted: JParted, filters: list, engine: Engine, ctx: Context, is_in_fn: bool
+) -> tuple[list[int] | None, list, list[tuple[str, str, J]]]:
+    def eval_value(node) -> J | None:
+        try:
+            return eval_node(node, engine, ctx, is_in_fn)
+        except JasmineEvalException:
+            # refers to other series
+            return None
+
+    candidates = []
+    for node in filters:
+        bin_op = downcast_ast_node(node)
+        conditions = []
+        if isinstance(bin_op, AstBinOp):
+            op = downcast_ast_node(bin_op.op)
+            lhs = downcast_ast_node(bin_op.lhs)
+            rhs = downcast_ast_node(bin_op.rhs)
+            if isinstance(lhs, AstId):
+                conditions.append((lhs.name, op.name, lambda v=rhs: eval_value(v)))
+            if isinstance(rhs, AstId):
+                conditions.append(
+                    (rhs.name, FLIPPED_OP.get(op.name), lambda v=lhs: eval_value(v))
+                )
+        candidates.append((node, conditions))
+    return prune_filters(parted, candidates)
+
+
+# filters with their candidate conditions (series, op, value fn), the value fn returns
+# none if the value is not known before scanning, returns pruned partitions, filters
+# left to apply while scanning and conditions used to skip chunks
+def prune_filters(
+    parted: JParted, filters: list[tuple[object, list]]
+) -> tuple[list[int] | None, list, list[tuple[str, str, J]]]:
+    nums = None
+    rest = []
+    conditions = []
+    unit = parted.get_unit()
+    for node, candidates in filters:
+        pruned = None
+        # the partition unit first
+        candidates = sorted(candidates, key=lambda c: c[0] != unit)
+        for series, op_name, to_value in candidates:
+            if op_name not in PRUNE_OP and op_name not in SKIP_OP:
+                continue
+            value = to_value()
+            if value is None:
+                continue
+            if series == unit:
+                pruned = prune(parted, op_name, value)
+            if (series != unit or not is_exact(parted)) and op_name in SKIP_OP:
+                conditions.append((series, op_name, value))
+            break
+        if pruned is None or not is_exact(parted):
+            rest.append(node)
+        if pruned is not None:
+            nums = pruned if nums is None else [n for n in nums if n in pruned]
+    return nums, rest, conditions
+
+
+# scans all partitions with a warning if none, chunks are skipped by their metadata,
+# returns the scan and its chunk files, none if all chunks are skipped
+def scan_partitions(
+    parted: JParted, nums: list[int] | None, conditions: list[tuple[str, str, J]]
+) -> tuple[pl.LazyFrame, list[str]]:
+    if parted.unit == "single":
+        return parted.scan(), [parted.path]
+    if nums is None:
+        warnings.warn(
+            "no condition on '%s', scan all partitions of %s"
+            % (parted.get_unit(), parted.path.name)
+        )
+        nums = parted.partitions
+    chunks = plan_chunks(parted, nums, conditions)
+    return parted.scan_chunks(chunks), [str(parted.get_chunk_file(c)) for c in chunks]
+
+
+# applies filters, groups and select|exec|update|delete to the lazy frame
+def eval_sql_query(
+    df: pl.LazyFrame,
+    op: str,
+    filters: list[pl.Expr],
+    groups: list[pl.Expr],
+    ops: list[pl.Expr],
+    engine: Engine,
+    source_id: int,
+    start: int,
+) -> pl.LazyFrame:
+    for filter in filters:
+        df = df.filter(filter)
+
+    if len(groups) > 0:
+        if op == "select" or op == "exec":
+            if len(ops) == 0:
+                df = df.group_by(groups, maintain_order=True).agg(pl.col("*").last())
+            else:
+                df = df.group_by(groups, maintain_order=True).agg(ops)
+        elif op == "update":
+            over_ops = []
+            for expr in ops:
+                over_ops.append(expr.over(groups))
+            df = df.with_columns(over_ops)
+        else:
+            raise JasmineEvalException(
+                engine.get_trace(source_id, start, "not support 'delete' with 'group'")
+            )
+    elif len(ops) > 0:
+        if op == "select" or op == "exec":
+            df = df.select(ops)
+        elif op == "update":
+            df = df.with_columns(ops)
+        else:
+            df = df.drop([expr.meta.output_name() for expr in ops])
+    return df
+
+
+# functional query, 'query(table, filters, groups, selects)'
+#   filters: list of functional expressions, e.g. l[l[>, `qty, 50]]
+#   groups, selects: dict of series name to functional expression, or none
+def eval_query(
+    table: J,
+    filters: J,
+    groups: J,
+    selects: J,
+    engine: Engine,
+    source_id: int,
+    start: int,
+) -> J:
+    if table.j_type == JType.DATAFRAME:
+        df = table.data.lazy()
+    elif table.j_type == JType.PARTED:
+        parted = table.data.pin()
+        items = filters.data if filters.j_type == JType.LIST else []
+        nums, rest, conditions = prune_filters(
+            parted, [(f, to_functional_conditions(f)) for f in items]
+        )
+        if filters.j_type == JType.LIST:
+            filters = J(rest, JType.LIST)
+        df, _ = scan_partitions(parted, nums, conditions)
+    else:
+        raise JasmineEvalException(
+            "'query' requires dataframe, got %s" % table.j_type.name
+        )
+
+    if filters.j_type == JType.NONE:
+        filters = []
+    elif filters.j_type == JType.LIST:
+        filters = [to_functional_expr(f) for f in filters.data]
+    else:
+        raise JasmineEvalException(
+            "'query' requires list of filters, got %s" % filters.j_type.name
+        )
+    groups = to_functional_exprs(groups, "groups")
+    selects = to_functional_exprs(selects, "selects")
+    df = eval_sql_query(
+        df, "select", filters, groups, selects, engine, source_id, start
+    )
+    return J(df.collect())
+
+
+# a functional filter comparing a series with a value, e.g. l[==, `sym, l[`a]]
+def to_functional_conditions(f: J) -> list:
+    if (
+        f.j_type == JType.LIST
+        and len(f.data) == 3
+        and f.data[0].j_type == JType.CAT
+        and f.data[1].j_type == JType.CAT
+    ):
+        op_name, series = f.data[0].data, f.data[1].data
+        value = to_functional_arg(op_name, f.data[2])
+        if isinstance(value, J):
+            return [(series, op_name, lambda: value)]
+    return []
+
+
+def to_functional_exprs(exps: J, name: str) -> list[pl.Expr]:
+    if exps.j_type == JType.NONE:
+        return []
+    elif exps.j_type == JType.DICT:
+        return [to_functional_expr(v).alias(k) for k, v in exps.data.items()]
+    else:
+        raise JasmineEvalException(
+            "'query' requires dict of %s, got %s" % (name, exps.j_type.name)
+        )
+
+
+# list with a leading fn name is a fn call, single item list is a value, cat is a series
+def to_functional_expr(exp: J) -> pl.Expr:
+    if exp.j_type == JType.CAT:
+        if exp.data == "i":
+            return pl.int_range(pl.len(), dtype=pl.UInt32).alias("i")
+        else:
+            return pl.col(exp.data)
+    elif exp.j_type == JType.LIST:
+        if len(exp.data) == 0:
+            raise JasmineEvalException("empty list is not a functional expression")
+        elif len(exp.data) == 1:
+            return exp.data[0].to_expr()
+        fn_name = exp.data[0]
+        if fn_name.j_type != JType.CAT:
+            raise JasmineEvalException(
+                "requires fn name as the first item, got %s" % fn_name.j_type.name
+            )
+        fn_name = fn_name.data
+        fn_args = [to_functional_arg(fn_name, arg) for arg in exp.data[1:]]
+        if fn_name not in SQL_FN:
+            raise JasmineEvalException("%s is not a valid sql fn" % fn_name)
+        err = check_sql_fn_args(fn_name, len(fn_args))
+        if err:
+            raise JasmineEvalException(err)
+        expr = eval_sql_fn(SQL_FN[fn_name], fn_name, *fn_args)
+        if isinstance(expr, J):
+            expr = expr.to_expr()
+        return expr
+    else:
+        return exp.to_expr()
+
+
+# single item list is a value, a list of values of 'in' and 'between' is a series, e.g.
+# l[in, `sym, l[`a, `b]]
+def to_functional_arg(fn_name: str, arg: J) -> J | pl.Expr:
+    if arg.j_type == JType.LIST and len(arg.data) == 1:
+        return arg.data[0]
+    elif arg.j_type == JType.LIST and fn_name in ("in", "between"):
+        return J(pl.Series("", [item.data for item in arg.data]))
+    elif arg.j_type == JType.CAT or arg.j_type == JType.LIST:
+        return to_functional_expr(arg)
+    else:
+        return arg
+
+
+JOIN_HOW = {
+    "left": "left",
+    "inner": "inner",
+    "outer": "full",
+    "anti": "anti",
+}
+
+
+# how: String,
+# right: Ast,
+# keys: Vec<Ast>,
+def eval_sql_join(
+    join: AstJoin,
+    df: pl.LazyFrame,
+    engine: Engine,
+    ctx: Context,
+    is_in_fn: bool,
+) -> pl.LazyFrame:
+    right = downcast_ast_node(join.right)
+    j = eval_sql_frame(right, engine, ctx, is_in_fn)
+    if isinstance(j, pl.LazyFrame):
+        other = j
+    elif j.j_type == JType.DATAFRAME:
+        other = j.data.lazy()
+    else:
+        raise JasmineEvalException(
+            engine.get_trace(
+                join.source_id,
+                join.start,
+                "'%s join' requires dataframe, got %s" % (join.how, j.j_type.name),
+            )
+        )
+    if join.how == "union":
+        return pl.concat([df, other], how="diagonal_relaxed")
+
+    keys = []
+    for node in join.keys:
+        key = eval_sql_op(node, engine, ctx, is_in_fn)
+        if isinstance(key, J):
+            key = key.to_expr()
+        keys.append(key)
+    # columns from the right dataframe colliding with the left one are suffixed by its name
+    suffix = "_" + right.name if isinstance(right, AstId) else "_right"
+    return df.join(
+        other, on=keys, how=JOIN_HOW[join.how], suffix=suffix, coalesce=True
+    )
 
 
 def eval_sql_op(
@@ -552,6 +919,10 @@ def eval_sql_op(
             return engine.globals[node.name]
         else:
             return pl.col(node.name)
+    elif isinstance(node, AstColumns):
+        # evaluated as variables instead of series names
+        j = eval_node(node.exp, engine, ctx, is_in_fn)
+        return columns(j, node.exclude, node.by_type)
     elif isinstance(node, AstUnaryOp):
         op = downcast_ast_node(node.op)
         exp = eval_sql_op(node.exp, engine, ctx, is_in_fn)
@@ -623,12 +994,29 @@ def eval_sql_fn(fn: Callable, fn_name: str, *args) -> pl.Expr:
             j = args[0]
             expr = args[1]
             return cast(j, J(expr))
-        case "between":
+        case "between" | "in" | "like" | "match":
             arg0 = args[0]
             arg1 = args[1]
             if isinstance(arg0, J):
                 arg0 = arg0.to_expr()
-            return fn(arg0, arg1)
+            if isinstance(arg1, J):
+                return fn(J(arg0), arg1)
+            elif fn_name == "between" and isinstance(arg1, list) and len(arg1) == 2:
+                return arg0.is_between(*arg1)
+            elif fn_name == "in" and isinstance(arg1, pl.Expr):
+                return arg0.is_in(arg1)
+            else:
+                raise JasmineEvalException(
+                    "'%s' requires values instead of series expression" % fn_name
+                )
+        case "xbar":
+            bucket = args[0]
+            if isinstance(bucket, J):
+                return fn(bucket, J(args[1]))
+            else:
+                raise JasmineEvalException(
+                    "'xbar' requires 'INT|FLOAT|DURATION|CAT' as bucket"
+                )
         case "tz":
             arg0 = args[0]
             arg1 = args[1]
@@ -647,20 +1035,40 @@ def eval_sql_fn(fn: Callable, fn_name: str, *args) -> pl.Expr:
             return fn(*fn_args)
 
 
+# sql fns as ints are not implemented yet, the int is the number of arguments
+def check_sql_fn_args(fn_name: str, arg_num: int) -> str | None:
+    fn = SQL_FN[fn_name]
+    if isinstance(fn, int):
+        return "'%s' is not supported in sql yet" % fn_name
+    try:
+        signature = inspect.signature(fn)
+    except ValueError:
+        return None
+    try:
+        signature.bind(*[None] * arg_num)
+    except TypeError:
+        required = [
+            p
+            for p in signature.parameters.values()
+            if p.default is p.empty and p.kind <= p.POSITIONAL_OR_KEYWORD
+        ]
+        return "'%s' takes %s arguments but %s were given" % (
+            fn_name,
+            len(required),
+            arg_num,
+        )
+    return None
+
+
 def get_sql_fn(node: AstOp | AstId, fn_name: str, arg_num: int, engine: Engine):
     if fn_name in SQL_FN:
-        fn = SQL_FN[fn_name]
-        if fn.__code__.co_argcount != arg_num:
+        err = check_sql_fn_args(fn_name, arg_num)
+        if err:
             raise JasmineEvalException(
-                engine.get_trace(
-                    node.source_id,
-                    node.start,
-                    "'%s' takes %s arguments but %s were given"
-                    % (fn_name, fn.__code__.co_argcount, arg_num),
-                )
+                engine.get_trace(node.source_id, node.start, err)
             )
 
-        return fn
+        return SQL_FN[fn_name]
     else:
         raise JasmineEvalException(
             engine.get_trace(
@@ -1,7 +1,41 @@
+import polars as pl
 import polars.selectors as cs
 
-from .j import J
+from .constant import PL_DATA_TYPE
+from .exceptions import JasmineEvalException
+from .j import J, JType
 
 
 def selector(column: J) -> J:
     return J(cs.matches(column.to_str()))
+
+
+# series referred by names(cat or cats), regex(string) or data type names
+def columns(column: J, exclude: bool, by_type: bool) -> pl.Expr:
+    if by_type:
+        dtypes = []
+        for name in to_names(column):
+            if name not in PL_DATA_TYPE:
+                raise JasmineEvalException("unknown data type for 'c[$]': %s" % name)
+            dtypes.append(PL_DATA_TYPE[name])
+        selected = cs.by_dtype(dtypes)
+    elif column.j_type == JType.STRING:
+        selected = cs.matches(column.data)
+    else:
+        selected = cs.by_name(to_names(column))
+    if exclude:
+        selected = ~selected
+    return selected.as_expr()
+
+
+def to_names(column: J) -> list[str]:
+    if column.j_type == JType.CAT or column.j_type == JType.STRING:
+        return [column.data]
+    elif column.j_type == JType.SERIES and (
+        column.data.dtype == pl.Categorical or column.data.dtype == pl.String
+    ):
+        return column.data.drop_nulls().to_list()
+    else:
+        raise JasmineEvalException(
+            "'c[]' requires cat|cats|string, got %s" % column.j_type.name
+        )
@@ -1,59 +1,102 @@
-import glob
-import os
-from pathlib import Path
-
 import polars as pl
 
+from .ast import compact_db, compact_syms, list_versions, verify_db, write_partition
 from .exceptions import JasmineEvalException
 from .j import J, JType
+from .prune import to_partition_num
+
+PARTITION_UNITS = ["year", "month", "date", "int"]
+
+TABLE_FORMATS = ["parquet", "ipc", "ipc_lz4"]
+
+
+# partition value -> (unit, partition, hashed series, buckets, format)
+#   date -> date, int -> int(or year|month of an existing table), none -> single file
+#   {year|month|date|int: value}, dates are truncated to the unit
+#   {hash: `series`, buckets: n}
+#   {..., format: `parquet|ipc|ipc_lz4`}, {format: ...} for a single file
+def to_partition(partition: J) -> tuple[str, int | None, str, int, str]:
+    partition.assert_types([JType.INT, JType.DATE, JType.NONE, JType.DICT])
+    match partition.j_type:
+        case JType.DATE:
+            return "date", partition.date_num(), "", 0, "parquet"
+        case JType.INT:
+            return "int", partition.data, "", 0, "parquet"
+        case JType.NONE:
+            return "single", None, "", 0, "parquet"
+    d = dict(partition.data)
+    fmt = "parquet"
+    if "format" in d:
+        fmt = d.pop("format").to_str()
+        if fmt not in TABLE_FORMATS:
+            raise JasmineEvalException("unknown table format '%s'" % fmt)
+        if len(d) == 0:
+            return "single", None, "", 0, fmt
+    if len(d) == 2 and "hash" in d and "buckets" in d:
+        d["buckets"].assert_type(JType.INT)
+        return "hash", None, d["hash"].to_str(), d["buckets"].data, fmt
+    if len(d) == 1:
+        unit, value = next(iter(d.items()))
+        num = to_partition_num(unit, value.data) if unit in PARTITION_UNITS else None
+        if num is not None:
+            return unit, num, "", 0, fmt
+    raise JasmineEvalException(
+        "expect '{year|month|date|int: value}' or '{hash: `series`, buckets: n}' "
+        "for partition, got %s" % partition
+    )
 
 
-# write partition df
+# write partition df as 'table/<partition>_<nnnn>', or 'table' if partition is none,
+# hash partitions are split into buckets and return paths of all written buckets
 def wpart(
     hdb_path: J, partition: J, table: J, df: J, sort_series: J, rechunk: J, overwrite: J
 ) -> J:
-    base_path = Path(hdb_path.to_str())
-    partition.assert_types([JType.INT, JType.DATE, JType.NONE])
-    partition = (
-        partition.date_num() if partition.j_type == JType.DATE else partition.data
+    unit, num, series, buckets, fmt = to_partition(partition)
+    paths = write_partition(
+        hdb_path.to_str(),
+        table.to_str(),
+        unit,
+        num,
+        series,
+        buckets,
+        fmt,
+        df.to_df(),
+        sort_series.to_strs(),
+        rechunk.to_bool(),
+        overwrite.to_bool(),
     )
-    sort_series = sort_series.to_strs()
-    rechunk = rechunk.to_bool()
-
-    table_path = base_path.joinpath(table.to_str())
-    if table_path.is_file() and partition is not None:
-        raise JasmineEvalException(
-            "single file exists, not allow partition '%s'", partition
-        )
-    if partition:
-        part_pattern = str(table_path) + str(partition) + "_*"
-        if overwrite:
-            table_path.mkdir(parents=True, exist_ok=True)
-            part_path = table_path.joinpath(str(partition) + "_0000")
-            for filepath in glob.glob(part_pattern):
-                os.remove(filepath)
-            df.to_df().sort(sort_series).write_parquet(part_path)
-            return J(str(part_path), JType.STRING)
-        else:
-            max_file_num = -1
-            table_path.mkdir(parents=True, exist_ok=True)
-            for filepath in glob.glob(part_pattern):
-                sub = int(filepath[-4:])
-                if sub > max_file_num:
-                    max_file_num = sub
-            part_path = table_path.joinpath(str(partition) + "_%04d" % max_file_num + 1)
-            df.to_df().sort(sort_series).write_parquet(part_path)
-            if rechunk and max_file_num > -1:
-                tmp_path = table_path.joinpath(str(partition) + "tmp")
-                pl.scan_parquet(part_pattern).sort(sort_series).sink_parquet(tmp_path)
-                for filepath in glob.glob(part_pattern):
-                    os.remove(filepath)
-                target_path = table_path.joinpath(str(partition) + "_0000")
-                os.rename(tmp_path, target_path)
-                return J(str(target_path), JType.STRING)
-            else:
-                return J(str(part_path), JType.STRING)
-    else:
-        base_path.mkdir(parents=True, exist_ok=True)
-        df.to_df().sort(sort_series).write_parquet(table_path)
-        return J(str(table_path))
+    if unit == "hash":
+        return J(pl.Series("", paths, pl.String))
+    return J(paths[0], JType.STRING)
+
+
+# removes symbols of the database not referred by any partition, returns the number of
+# removed symbols
+def compactsym(hdb_path: J) -> J:
+    return J(compact_syms(hdb_path.to_str()), JType.INT)
+
+
+# issues of a database as a dataframe of table, path and issue, empty if none
+def verifydb(hdb_path: J) -> J:
+    return J(verify_db(hdb_path.to_str()))
+
+
+# merges chunks of each partition into one sorted file by its recorded sort series and
+# removes orphan temp files, returns paths of merged partitions
+def compactdb(hdb_path: J) -> J:
+    return J(pl.Series("", compact_db(hdb_path.to_str()), pl.String))
+
+
+# a partitioned table reading one of its kept versions, later writes are not visible
+def snapshot(table: J, version: J) -> J:
+    table.assert_type(JType.PARTED)
+    version.assert_type(JType.INT)
+    if table.data.unit == "single":
+        raise JasmineEvalException("'snapshot' requires partitioned table, got a file")
+    return J(table.data.pin(version.data))
+
+
+# kept versions of a partitioned table, the last is the latest
+def versions(table: J) -> J:
+    table.assert_type(JType.PARTED)
+    return J(pl.Series("", list_versions(str(table.data.path)), pl.Int64))
@@ -1,11 +1,10 @@
 from datetime import date
 from enum import Enum
 from pathlib import Path
-from typing import Literal
 
 import polars as pl
 
-from .ast import JObj
+from .ast import JObj, pin_table, read_pin, read_syms, scan_table
 from .exceptions import JasmineEvalException
 from .j_fn import JFn
 from .util import date_to_num
@@ -38,45 +37,105 @@ class JType(Enum):
 
 class JParted:
     path: Path
-    # single=0, date=4, year=8
-    unit: int
+    # single|year|month|date|int|hash
+    unit: str
+    # series referring to partitions, the hashed series for hash partitions
+    series: str
+    buckets: int
+    # categorical series stored as codes of a database dictionary, 'db/.sym'
+    enums: list[str]
     partitions: list[int]
+    # version of 'table/.manifest' to read, the latest if none
+    version: int | None
+    # (partition, chunk) of the version in scan order, read by 'pin'
+    chunks: list[tuple[int, int]] | None
+    # symbol dictionary of the version, read by 'pin'
+    sym: str | None
+    # the pinned table, chunk files of the version are kept while it is alive
+    obj: JObj | None
 
     def __init__(
-        self, path: Path, unit: Literal[0, 4, 8], partitions: list[int]
+        self,
+        path: Path,
+        unit: str,
+        series: str,
+        buckets: int,
+        enums: list[str],
+        partitions: list[int],
+        version: int | None = None,
     ) -> None:
         self.path = path
         self.unit = unit
+        self.series = series
+        self.buckets = buckets
+        self.enums = enums
         self.partitions = partitions
+        self.version = version
+        self.chunks = None
+        self.sym = None
+        self.obj = None
 
     def get_unit(self) -> str:
-        match self.unit:
-            case 4:
-                return "year"
-            case 8:
-                return "date"
-            case _:
-                return "single"
+        return self.series
 
     def __str__(self) -> str:
-        unit = self.get_unit()
-        return f"partitioned by {unit} @ `{self.path}` - {self.partitions[-3:]}"
-
-    def get_partition_paths(self, start: int, end: int) -> list[str]:
-        paths = []
-        for partition in filter(lambda x: x >= start and x <= end, self.partitions):
-            paths.append(self.path.joinpath(str(partition) + "*"))
-        return paths
-
-    def get_latest_path(self) -> str:
-        return self.path.joinpath(str(self.partitions[-1]) + "*")
-
-    def get_partition_paths_by_date_nums(self, nums: list[int]) -> list[str]:
-        paths = []
-        for num in nums:
-            if num in self.partitions:
-                paths.append(self.path.joinpath(str(num) + "*"))
-        return paths
+        unit = self.unit
+        if unit == "hash":
+            unit = "hash(%s) in %s buckets" % (self.series, self.buckets)
+        version = "" if self.version is None else " v%s" % self.version
+        return (
+            f"partitioned by {unit} @ `{self.path}`{version} - {self.partitions[-3:]}"
+        )
+
+    # a copy reading chunks of one version, a query reads the same version even if
+    # writers publish new ones
+    def pin(self, version: int | None = None) -> "JParted":
+        if self.unit == "single" or (self.chunks is not None and version is None):
+            return self
+        if version is None:
+            version = self.version
+        obj = pin_table(str(self.path), version)
+        version, chunks, sym = read_pin(obj)
+        pinned = JParted(
+            self.path,
+            self.unit,
+            self.series,
+            self.buckets,
+            self.enums,
+            sorted({partition for partition, _ in chunks}),
+            version,
+        )
+        pinned.chunks = chunks
+        pinned.sym = sym
+        pinned.obj = obj
+        return pinned
+
+    # (partition, chunk) of partitions in scan order of the pinned version
+    def get_chunks(self, nums: list[int]) -> list[tuple[int, int]]:
+        chunks = self.pin().chunks
+        return [chunk for num in nums for chunk in chunks if chunk[0] == num]
+
+    def get_chunk_file(self, chunk: tuple[int, int]) -> Path:
+        return self.path.joinpath("%s_%04d" % chunk)
+
+    # scan all partitions if nums is None, partitions of different schemas are scanned
+    # as the unified one, enumerated series are decoded as categorical series
+    def scan(self, nums: list[int] | None = None) -> pl.LazyFrame:
+        if self.unit == "single":
+            return scan_table(pin_table(str(self.path)))
+        parted = self.pin()
+        if nums is None:
+            nums = parted.partitions
+        return parted.scan_chunks(parted.get_chunks(nums))
+
+    # chunks of the pinned version, an empty frame of the latest partition if none
+    def scan_chunks(self, chunks: list[tuple[int, int]]) -> pl.LazyFrame:
+        return scan_table(self.pin().obj, chunks)
+
+    # symbols of the dictionary of the pinned version, read every time as writers append
+    # new symbols
+    def get_syms(self) -> list[str]:
+        return read_syms(str(self.path.parent), self.pin().sym)
 
 
 class J:
@@ -90,6 +149,19 @@ class J:
             match self.j_type:
                 case JType.DATETIME | JType.TIMESTAMP:
                     self.data = data
+                case JType.PARTED:
+                    path, unit, series, buckets, enums, partitions, version = (
+                        data.as_py()
+                    )
+                    self.data = JParted(
+                        Path(path), unit, series, buckets, enums, partitions, version
+                    )
+                case JType.LIST:
+                    self.data = [J(item) for item in data.as_list()]
+                case JType.DICT:
+                    self.data = {k: J(v) for k, v in data.as_dict()}
+                case JType.ERR:
+                    self.data = data.as_err()
                 case _:
                     self.data = data.as_py()
         elif isinstance(data, pl.Series):
@@ -149,6 +221,10 @@ class J:
                 return f"{neg}{days}D{HH:02d}:{mm:02d}:{ss:02d}:{sss:09d}"
             case JType.PARTED:
                 return str(self.data)
+            case JType.LIST:
+                return "l[%s]" % ", ".join(str(j) for j in self.data)
+            case JType.DICT:
+                return "\n".join(f"{k}: {v}" for k, v in self.data.items())
             case _:
                 return repr(self)
 
@@ -319,7 +395,7 @@ class J:
             )
 
     def assert_type(self, type: JType):
-        if self.j_type == type:
+        if self.j_type != type:
             raise JasmineEvalException(
                 "expect '%s', but got %s" % (type.name, self.j_type)
             )
@@ -0,0 +1,150 @@
+import polars as pl
+
+from .exceptions import JasmineEvalException
+from .j import J, JType
+from .prune import prune
+
+AGG_FN = {
+    "count": pl.Expr.count,
+    "first": pl.Expr.first,
+    "last": pl.Expr.last,
+    "max": pl.Expr.max,
+    "mean": pl.Expr.mean,
+    "median": pl.Expr.median,
+    "min": pl.Expr.min,
+    "sum": pl.Expr.sum,
+}
+
+
+def to_lazy(j: J, left: pl.LazyFrame | None = None) -> pl.LazyFrame:
+    j.assert_types([JType.DATAFRAME, JType.PARTED])
+    if j.j_type == JType.DATAFRAME:
+        return j.data.lazy()
+    parted = j.data.pin()
+    unit = parted.get_unit()
+    # only scan partitions referred by the left dataframe
+    if left is not None and parted.unit != "single" and unit in left.collect_schema():
+        values = left.select(pl.col(unit).unique()).collect().to_series()
+        return parted.scan(prune(parted, "in", J(values)))
+    else:
+        return parted.scan()
+
+
+def to_join_keys(on: J) -> tuple[list[str], str]:
+    keys = on.to_strs()
+    if len(keys) == 0:
+        raise JasmineEvalException("requires at least a time series name for 'on'")
+    return keys[:-1], keys[-1]
+
+
+def to_offset(j: J) -> pl.Expr:
+    if j.j_type == JType.DURATION:
+        return pl.duration(nanoseconds=j.data)
+    elif j.j_type == JType.INT or j.j_type == JType.FLOAT:
+        return pl.lit(j.data)
+    else:
+        raise JasmineEvalException(
+            "expect 'DURATION|INT|FLOAT' for window, but got %s" % j.j_type.name
+        )
+
+
+# as-of join, the last series of 'on' is the sorted time series, others are by series
+def aj(on: J, left: J, right: J, tolerance: J, direction: J) -> J:
+    by, time = to_join_keys(on)
+    tolerance.assert_types([JType.NONE, JType.DURATION, JType.INT, JType.FLOAT])
+    direction.assert_types([JType.NONE, JType.CAT, JType.STRING])
+    strategy = "backward" if direction.j_type == JType.NONE else direction.to_str()
+    if strategy not in ("backward", "forward", "nearest"):
+        raise JasmineEvalException(
+            "expect 'backward|forward|nearest' for direction, but got %s" % strategy
+        )
+    match tolerance.j_type:
+        case JType.NONE:
+            tolerance = None
+        case JType.DURATION:
+            tolerance = "%dns" % tolerance.data
+        case _:
+            tolerance = tolerance.data
+
+    left_df = to_lazy(left).with_row_index("__i")
+    right_df = to_lazy(right, left_df).sort(time)
+    df = (
+        left_df.sort(time)
+        .join_asof(
+            right_df,
+            on=time,
+            by=by if len(by) > 0 else None,
+            strategy=strategy,
+            tolerance=tolerance,
+        )
+        .sort("__i")
+        .drop("__i")
+    )
+    return J(df.collect())
+
+
+# window join, aggregates right series within [time + window[0], time + window[1]]
+def wj(window: J, on: J, left: J, right: J, aggs: J) -> J:
+    by, time = to_join_keys(on)
+    window.assert_types([JType.SERIES])
+    if window.data.len() != 2 or window.data.null_count() > 0:
+        raise JasmineEvalException("requires 2 non-null values for window")
+    if window.data.dtype == pl.Duration:
+        window = [J(v, JType.DURATION) for v in window.data.cast(pl.Int64)]
+    else:
+        window = [J(v, JType.INT) for v in window.data]
+    aggs.assert_types([JType.DICT])
+    agg_exprs = []
+    counts = []
+    for name, fn_name in aggs.data.items():
+        fn_name = fn_name.to_str()
+        if fn_name not in AGG_FN:
+            raise JasmineEvalException(
+                "expect '%s' for aggregation, but got %s"
+                % ("|".join(AGG_FN.keys()), fn_name)
+            )
+        agg_exprs.append(AGG_FN[fn_name](pl.col(name)))
+        if fn_name == "count":
+            counts.append(name)
+
+    left_df = to_lazy(left).with_row_index("__i")
+    # row indices of rows in the same by group are continuous and sorted by time
+    right_df = to_lazy(right, left_df).sort(by + [time]).with_row_index("__j")
+    # rows of equal time stay in order, the first and the last of them bound windows
+    right_keys = right_df.select(by + [pl.col(time).alias("__t"), "__j"]).sort(
+        ["__t", "__j"]
+    )
+    bounds = left_df.select(
+        ["__i"] + by,
+        (pl.col(time) + to_offset(window[0])).alias("__lo"),
+        (pl.col(time) + to_offset(window[1])).alias("__hi"),
+    )
+    by = by if len(by) > 0 else None
+    starts = (
+        bounds.sort("__lo")
+        .join_asof(right_keys, left_on="__lo", right_on="__t", by=by, strategy="forward")
+        .select("__i", pl.col("__j").alias("__start"))
+    )
+    ends = (
+        bounds.sort("__hi")
+        .join_asof(right_keys, left_on="__hi", right_on="__t", by=by, strategy="backward")
+        .select("__i", pl.col("__j").alias("__end"))
+    )
+    windows = (
+        starts.join(ends, on="__i")
+        .filter(pl.col("__start") <= pl.col("__end"))
+        .select("__i", pl.int_ranges("__start", pl.col("__end") + 1).alias("__j"))
+        .explode("__j")
+        .with_columns(pl.col("__j").cast(pl.UInt32))
+        .join(right_df, on="__j")
+        .group_by("__i")
+        .agg(agg_exprs)
+    )
+    # empty windows count no rows
+    df = (
+        left_df.join(windows, on="__i", how="left")
+        .with_columns([pl.col(name).fill_null(0) for name in counts])
+        .sort("__i")
+        .drop("__i")
+    )
+    return J(df.collect())
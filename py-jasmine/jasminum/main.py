@@ -9,6 +9,7 @@ from .context import Context
 from .engine import Engine
 from .eval import SQL_FN, eval_src
 from .history_console import HistoryConsole
+from .server import MAX_PAYLOAD, start_server
 
 __version__ = importlib.metadata.version("jasminum")
 
@@ -23,6 +24,30 @@ parser.add_argument(
     help="enable debug mode",
 )
 
+parser.add_argument(
+    "-p",
+    "--port",
+    type=int,
+    default=None,
+    dest="port",
+    help="serve messages on the port",
+)
+
+parser.add_argument(
+    "--host",
+    default="127.0.0.1",
+    dest="host",
+    help="serve messages on the host, only local connections by default",
+)
+
+parser.add_argument(
+    "--max-payload",
+    type=int,
+    default=MAX_PAYLOAD,
+    dest="max_payload",
+    help="close connections sending messages larger than the bytes",
+)
+
 
 def main():
     args = parser.parse_args()
@@ -46,6 +71,9 @@ def main():
     )
 
     engine = Engine()
+    if args.port is not None:
+        server = start_server(engine, args.host, args.port, args.max_payload)
+        cprint("listening on %s:%s" % server.server_address, "light_green")
     HistoryConsole()
     src = ""
     readline.set_completer(complete)
@@ -65,7 +93,8 @@ def main():
             src = "\n".join(src)
             engine.sources[0] = (src, "")
             try:
-                res = eval_src(src, 0, engine, Context(dict()))
+                with engine.lock:
+                    res = eval_src(src, 0, engine, Context(dict()))
                 cprint(res, "light_green")
             except Exception as e:
                 if args.debug:
@@ -0,0 +1,94 @@
+import polars as pl
+
+from .j import J, JParted, JType
+
+# conditions 'series op value' used to skip chunks by their min/max
+SKIP_OP = {"==", "<", "<=", ">", ">=", "between"}
+
+# a symbol not in the database symbols, no chunk matches it
+MISSING_SYM = object()
+
+
+# chunk metadata written by 'wpart', 'table/.meta', keyed by (partition, chunk)
+def read_meta(parted: JParted) -> dict[tuple[int, int], dict]:
+    path = parted.path.joinpath(".meta")
+    if not path.exists():
+        return {}
+    return {
+        (row["partition"], row["chunk"]): row
+        for row in pl.read_parquet(path).to_dicts()
+    }
+
+
+# python value of a condition, codes for enumerated series, none if not supported
+def to_value(parted: JParted, series: str, op: str, value: J, syms: list[str]):
+    if value.j_type in [JType.INT, JType.FLOAT, JType.DATE, JType.STRING, JType.CAT]:
+        v = value.data
+    elif (
+        value.j_type == JType.SERIES
+        and op == "between"
+        and value.data.len() == 2
+        and value.data.null_count() == 0
+    ):
+        v = value.data.to_list()
+    else:
+        return None
+    if op == "between" and not isinstance(v, list):
+        return None
+    if series in parted.enums:
+        # codes are only equal for the same symbol
+        if op != "==" or not isinstance(v, str):
+            return None
+        return syms.index(v) if v in syms else MISSING_SYM
+    return v
+
+
+def may_match(row: dict, series: str, op: str, v) -> bool:
+    if v is MISSING_SYM:
+        return False
+    lo, hi = row.get(series + ".min"), row.get(series + ".max")
+    if lo is None or hi is None:
+        return True
+    try:
+        match op:
+            case "==":
+                return lo <= v <= hi
+            case "<":
+                return lo < v
+            case "<=":
+                return lo <= v
+            case ">":
+                return hi > v
+            case ">=":
+                return hi >= v
+            case "between":
+                return hi >= v[0] and lo <= v[1]
+    except TypeError:
+        return True
+    return True
+
+
+# (partition, chunk) of partitions, chunks whose metadata cannot match conditions are
+# skipped without reading them, rows of the others are filtered by the lazy scan
+def plan_chunks(
+    parted: JParted, nums: list[int], conditions: list[tuple[str, str, J]]
+) -> list[tuple[int, int]]:
+    meta = read_meta(parted)
+    syms = (
+        parted.get_syms()
+        if any(series in parted.enums for series, _, _ in conditions)
+        else []
+    )
+    conds = []
+    for series, op, value in conditions:
+        v = to_value(parted, series, op, value, syms)
+        if v is not None:
+            conds.append((series, op, v))
+    chunks = []
+    for chunk in parted.get_chunks(nums):
+        row = meta.get(chunk)
+        if row is None or all(
+            may_match(row, series, op, v) for series, op, v in conds
+        ):
+            chunks.append(chunk)
+    return chunks
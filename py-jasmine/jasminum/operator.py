@@ -1,3 +1,4 @@
+import re
 from datetime import timedelta
 
 import numpy as np
@@ -152,3 +153,103 @@ def cast(type_name: J, arg: J) -> J:
                     return J(arg.data.dt.millisecond())
                 case "ns":
                     return J(arg.data.dt.nanosecond())
+
+
+CALENDAR_INTERVAL = re.compile(r"^\d+(w|mo|q|y)$")
+
+TIME_UNIT_IN_NS = {"ns": 1, "us": 1_000, "ms": 1_000_000}
+
+
+# floor to a multiple of bucket
+# calendar interval(w, mo, q, y) as cat bucket for date, datetime and timestamp
+def xbar(bucket: J, arg: J) -> J | pl.Expr:
+    bucket.assert_types(
+        [JType.INT, JType.FLOAT, JType.DURATION, JType.CAT, JType.STRING]
+    )
+    match arg.j_type:
+        case JType.EXPR:
+            return arg.data.map_batches(
+                lambda s: xbar_series(bucket, s), is_elementwise=True
+            )
+        case JType.SERIES:
+            return J(xbar_series(bucket, arg.data))
+        case JType.INT | JType.FLOAT:
+            s = xbar_series(bucket, arg.to_series())
+            return J(s[0], JType.INT if s.dtype.is_integer() else JType.FLOAT)
+        case JType.DATE:
+            return J(xbar_series(bucket, arg.to_series())[0])
+        case JType.TIME | JType.DURATION:
+            s = xbar_series(bucket, arg.to_series())
+            return J(s.cast(pl.Int64)[0], arg.j_type)
+        case JType.DATETIME:
+            s = xbar_series(bucket, arg.to_series())
+            return J.from_millis(s.cast(pl.Int64)[0], arg.tz())
+        case JType.TIMESTAMP:
+            s = xbar_series(bucket, arg.to_series())
+            return J.from_nanos(s.cast(pl.Int64)[0], arg.tz())
+        case _:
+            raise JasmineEvalException(
+                "'xbar' requires numeric or temporal value, got %s" % arg.j_type.name
+            )
+
+
+def xbar_series(bucket: J, s: pl.Series) -> pl.Series:
+    dtype = s.dtype
+    if bucket.j_type == JType.CAT or bucket.j_type == JType.STRING:
+        interval = bucket.to_str()
+        if not CALENDAR_INTERVAL.match(interval):
+            raise JasmineEvalException(
+                "expect calendar interval 'w|mo|q|y' for 'xbar', got %s" % interval
+            )
+        if dtype != pl.Date and not isinstance(dtype, pl.Datetime):
+            raise JasmineEvalException(
+                "calendar interval requires date, datetime or timestamp, got %s" % dtype
+            )
+        # truncated in local time for datetime with timezone
+        return s.dt.truncate(interval)
+    elif dtype.is_numeric():
+        if bucket.j_type == JType.DURATION:
+            raise JasmineEvalException("'duration' bucket requires temporal series")
+        size = bucket.data
+        if size <= 0:
+            raise JasmineEvalException("requires positive bucket size for 'xbar'")
+        if dtype.is_integer() and bucket.j_type == JType.INT:
+            return s // size * size
+        else:
+            return (s / size).floor() * size
+
+    if bucket.j_type != JType.DURATION:
+        raise JasmineEvalException(
+            "requires 'duration' bucket for %s, got %s" % (dtype, bucket.j_type.name)
+        )
+    ns = bucket.data
+    if ns <= 0:
+        raise JasmineEvalException("requires positive bucket size for 'xbar'")
+    if dtype == pl.Date:
+        if ns % 86_400_000_000_000 != 0:
+            raise JasmineEvalException("requires whole days bucket for date")
+        days = ns // 86_400_000_000_000
+        return (s.cast(pl.Int32) // days * days).cast(pl.Date)
+    elif dtype == pl.Time:
+        return (s.cast(pl.Int64) // ns * ns).cast(pl.Time)
+    elif isinstance(dtype, pl.Duration) or isinstance(dtype, pl.Datetime):
+        unit_ns = TIME_UNIT_IN_NS[dtype.time_unit]
+        if ns % unit_ns != 0:
+            raise JasmineEvalException(
+                "bucket is finer than time unit '%s'" % dtype.time_unit
+            )
+        size = ns // unit_ns
+        if isinstance(dtype, pl.Duration):
+            return (s.cast(pl.Int64) // size * size).cast(dtype)
+        # floor on local time, e.g. 1D buckets start at local midnight
+        time_zone = dtype.time_zone
+        if time_zone:
+            s = s.dt.replace_time_zone(None)
+        floored = (s.cast(pl.Int64) // size * size).cast(pl.Datetime(dtype.time_unit))
+        if time_zone:
+            floored = floored.dt.replace_time_zone(time_zone, ambiguous="earliest")
+        return floored
+    else:
+        raise JasmineEvalException(
+            "'xbar' requires numeric or temporal series, got %s" % dtype
+        )
@@ -0,0 +1,92 @@
+import re
+
+import polars as pl
+
+from .exceptions import JasmineEvalException
+from .j import J, JType
+
+
+# cat values are compared as strings, so 'cat' series match both 'cat' and 'string' values
+def to_values(values: J) -> pl.Series:
+    if values.j_type == JType.SERIES:
+        series = values.data
+    else:
+        series = values.to_series()
+    if series.dtype == pl.Categorical:
+        series = series.cast(pl.String)
+    return series
+
+
+def to_pattern(pattern: J) -> str:
+    pattern.assert_types([JType.STRING, JType.CAT])
+    return pattern.data
+
+
+# *: any characters, ?: any character, [abc]/[a-c]/[!abc]: any/none of characters,
+# '[' without a closing ']' is a literal
+def glob_to_regex(pattern: str) -> str:
+    regex = "^"
+    i = 0
+    while i < len(pattern):
+        c = pattern[i]
+        if c == "*":
+            regex += ".*"
+        elif c == "?":
+            regex += "."
+        elif c == "[":
+            start = i + 1
+            if start < len(pattern) and pattern[start] == "!":
+                start += 1
+            # a ']' right after '[' or '[!' is a character of the set
+            end = pattern.find("]", start + 1)
+            if end < 0:
+                regex += re.escape(c)
+            else:
+                regex += "[^" if start > i + 1 else "["
+                for b in pattern[start:end]:
+                    regex += "\\" + b if b in "\\^[]" else b
+                regex += "]"
+                i = end
+        else:
+            regex += re.escape(c)
+        i += 1
+    return regex + "$"
+
+
+def apply_predicate(arg: J, fn) -> J | pl.Expr:
+    match arg.j_type:
+        case JType.EXPR:
+            return fn(arg.data)
+        case JType.SERIES:
+            return J(fn(arg.data))
+        case JType.DATAFRAME | JType.LIST | JType.DICT | JType.MATRIX | JType.FN:
+            raise JasmineEvalException(
+                "not supported j type for predicate: %s" % arg.j_type.name
+            )
+        case _:
+            return J(fn(arg.to_series())[0], JType.BOOLEAN)
+
+
+def is_in(arg: J, values: J) -> J | pl.Expr:
+    values = to_values(values)
+    return apply_predicate(arg, lambda x: x.is_in(values))
+
+
+def between(arg: J, bounds: J) -> J | pl.Expr:
+    bounds = to_values(bounds)
+    if bounds.len() != 2:
+        raise JasmineEvalException(
+            "'between' requires 2 values as bounds, got %s" % bounds.len()
+        )
+    return apply_predicate(arg, lambda x: x.is_between(bounds[0], bounds[1]))
+
+
+def like(arg: J, pattern: J) -> J | pl.Expr:
+    regex = glob_to_regex(to_pattern(pattern))
+    return apply_predicate(arg, lambda x: x.cast(pl.String).str.contains(regex))
+
+
+# a regex search, matches any part of a value unless anchored by '^' and '$'
+def match(arg: J, pattern: J) -> J | pl.Expr:
+    regex = to_pattern(pattern)
+    return apply_predicate(arg, lambda x: x.cast(pl.String).str.contains(regex))
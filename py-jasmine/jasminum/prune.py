@@ -0,0 +1,99 @@
+from datetime import date
+
+import polars as pl
+
+from .ast import hash_bucket
+from .j import J, JParted, JType
+from .util import date_to_num
+
+# operators with the partition unit on the right side
+FLIPPED_OP = {
+    "==": "==",
+    "!=": "!=",
+    "<": ">",
+    "<=": ">=",
+    ">": "<",
+    ">=": "<=",
+}
+
+PRUNE_OP = set(FLIPPED_OP.keys()) | {"in", "between"}
+
+
+def to_partition_num(unit: str, value) -> int | str | None:
+    if isinstance(value, bool):
+        return None
+    if isinstance(value, date):
+        match unit:
+            case "date":
+                return date_to_num(value)
+            case "month":
+                return value.year * 100 + value.month
+            case "year":
+                return value.year
+    elif isinstance(value, int) and unit in ["year", "month", "int"]:
+        return value
+    elif isinstance(value, str) and unit == "hash":
+        return value
+    return None
+
+
+def to_partition_nums(parted: JParted, value: J) -> list[int | str] | None:
+    if value.j_type == JType.SERIES:
+        values = value.data.drop_nulls().to_list()
+    elif value.j_type in [JType.DATE, JType.INT, JType.STRING, JType.CAT]:
+        values = [value.data]
+    else:
+        return None
+    nums = []
+    for v in values:
+        num = to_partition_num(parted.unit, v)
+        if num is None:
+            return None
+        nums.append(num)
+    return nums
+
+
+# partitions satisfying 'unit op value', none if not supported, partitions of hashed
+# values are only for '==' and 'in'
+def prune(parted: JParted, op_name: str, value: J) -> list[int] | None:
+    if op_name not in PRUNE_OP:
+        return None
+    if value.j_type == JType.SERIES and value.data.dtype == pl.Null:
+        return []
+    nums = to_partition_nums(parted, value)
+    if nums is None:
+        return None
+    partitions = parted.partitions
+    if parted.unit == "hash":
+        if op_name not in ["==", "in"]:
+            return None
+        buckets = {hash_bucket(v, parted.buckets) for v in nums}
+        return [p for p in partitions if p in buckets]
+    match op_name:
+        case "in":
+            return [p for p in partitions if p in nums]
+        case "between":
+            if len(nums) != 2:
+                return None
+            return [p for p in partitions if p >= nums[0] and p <= nums[1]]
+    if len(nums) != 1:
+        return None
+    num = nums[0]
+    match op_name:
+        case "==":
+            return [p for p in partitions if p == num]
+        case "!=":
+            return [p for p in partitions if p != num]
+        case "<":
+            return [p for p in partitions if p < num]
+        case "<=":
+            return [p for p in partitions if p <= num]
+        case ">":
+            return [p for p in partitions if p > num]
+        case ">=":
+            return [p for p in partitions if p >= num]
+
+
+# hashed series are kept in filters, a bucket holds other values too
+def is_exact(parted: JParted) -> bool:
+    return parted.unit != "hash"
@@ -0,0 +1,60 @@
+import polars as pl
+
+from .exceptions import JasmineEvalException
+from .j import J, JType
+
+PIVOT_AGG = ["first", "last", "len", "max", "mean", "median", "min", "sum"]
+
+
+def to_optional_strs(j: J) -> list[str] | None:
+    return None if j.j_type == JType.NONE else j.to_strs()
+
+
+# partitioned tables are read as a whole
+def to_df(j: J) -> pl.DataFrame:
+    j.assert_types([JType.DATAFRAME, JType.PARTED])
+    if j.j_type == JType.PARTED:
+        return j.data.scan().collect()
+    return j.data
+
+
+# long to wide, values of 'on' series become series names
+def pivot(df: J, on: J, index: J, values: J, agg: J) -> J:
+    df = to_df(df)
+    agg.assert_types([JType.NONE, JType.CAT, JType.STRING])
+    agg_fn = None
+    if agg.j_type != JType.NONE:
+        agg_fn = agg.to_str()
+        if agg_fn not in PIVOT_AGG:
+            raise JasmineEvalException(
+                "expect one of %s for 'pivot', but got %s"
+                % ("|".join(PIVOT_AGG), agg_fn)
+            )
+    return J(
+        df.pivot(
+            on=on.to_strs(),
+            index=to_optional_strs(index),
+            values=to_optional_strs(values),
+            aggregate_function=agg_fn,
+            maintain_order=True,
+        )
+    )
+
+
+# wide to long, unpivots all series other than 'index' if 'on' is none
+def unpivot(df: J, index: J, on: J) -> J:
+    return J(
+        to_df(df).unpivot(
+            on=to_optional_strs(on),
+            index=to_optional_strs(index),
+        )
+    )
+
+
+# explodes all list series together, e.g. after a grouped select
+def ungroup(df: J) -> J:
+    df = to_df(df)
+    lists = [name for name, dtype in df.schema.items() if isinstance(dtype, pl.List)]
+    if len(lists) == 0:
+        return J(df)
+    return J(df.explode(lists))
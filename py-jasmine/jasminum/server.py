@@ -0,0 +1,229 @@
+import socket
+import socketserver
+import struct
+import sys
+import threading
+from contextlib import AbstractContextManager, nullcontext
+
+from .ast import deserialize, serialize
+from .context import Context
+from .engine import Engine
+from .eval import eval_fn, eval_src
+from .exceptions import JasmineEvalException
+from .j import J, JType
+from .j_fn import JFn
+
+# a message is an 8 bytes header followed by its payload
+#   0: message type
+#   1: payload type
+#   2-3: reserved, 0
+#   4-7: payload length, u32 little endian
+HEADER = struct.Struct("<BBxxI")
+
+# message types, async messages are evaluated without a response
+ASYNC = 0
+SYNC = 1
+RESPONSE = 2
+
+# payload types
+#   source: source code in utf8
+#   serialized: a serialized J, a list of a function name followed by its arguments, or a string
+#      of source code
+SOURCE = 0
+SERIALIZED = 1
+
+# source id of remote messages, for error traces
+REMOTE_SOURCE_ID = -1
+
+# the default limit of payload length, as the length is sent by the peer
+MAX_PAYLOAD = 1 << 30
+
+
+# the payload is left unread, the connection can't be used after it
+class PayloadTooLarge(ConnectionError):
+    def __init__(self, msg_type: int, length: int, max_payload: int) -> None:
+        super().__init__(
+            "message of %s bytes exceeds max payload %s bytes" % (length, max_payload)
+        )
+        self.msg_type = msg_type
+
+
+def write_message(
+    sock: socket.socket, msg_type: int, payload_type: int, payload: bytes
+) -> None:
+    if len(payload) > 0xFFFFFFFF:
+        raise JasmineEvalException(
+            "message of %s bytes exceeds %s bytes" % (len(payload), 0xFFFFFFFF)
+        )
+    sock.sendall(HEADER.pack(msg_type, payload_type, len(payload)) + payload)
+
+
+def read_exact(sock: socket.socket, n: int) -> bytes:
+    buf = bytearray()
+    while len(buf) < n:
+        chunk = sock.recv(min(n - len(buf), 1 << 20))
+        if not chunk:
+            raise ConnectionError(
+                "connection closed after %s of %s bytes" % (len(buf), n)
+            )
+        buf += chunk
+    return bytes(buf)
+
+
+# (message type, payload type, payload), none if the peer closed the connection, raises
+# PayloadTooLarge before reading a payload longer than max_payload
+def read_message(
+    sock: socket.socket, max_payload: int = MAX_PAYLOAD
+) -> tuple[int, int, bytes] | None:
+    first = sock.recv(1)
+    if not first:
+        return None
+    msg_type, payload_type, length = HEADER.unpack(
+        first + read_exact(sock, HEADER.size - 1)
+    )
+    if length > max_payload:
+        raise PayloadTooLarge(msg_type, length, max_payload)
+    return msg_type, payload_type, read_exact(sock, length)
+
+
+# ':host:port' or 'host:port', host defaults to localhost
+def connect(address: str) -> socket.socket:
+    host, _, port = address.removeprefix(":").rpartition(":")
+    if not port.isdigit():
+        raise JasmineEvalException(
+            "invalid address '%s', expect ':host:port'" % address
+        )
+    return socket.create_connection((host or "localhost", int(port)))
+
+
+# sends a serialized J as a sync message, returns the deserialized response, raises
+# PayloadTooLarge if the response is longer than max_payload
+def call(sock: socket.socket, j: J, max_payload: int = MAX_PAYLOAD) -> J:
+    write_message(sock, SYNC, SERIALIZED, serialize(j))
+    msg = read_message(sock, max_payload)
+    if msg is None:
+        raise ConnectionError("connection closed by remote")
+    return J(deserialize(msg[2]))
+
+
+def eval_message(payload_type: int, payload: bytes, engine: Engine) -> J:
+    if payload_type == SOURCE:
+        return eval_remote_src(payload.decode(), engine)
+    elif payload_type != SERIALIZED:
+        raise JasmineEvalException("unknown payload type %s" % payload_type)
+    call = J(deserialize(payload))
+    if call.j_type == JType.STRING:
+        return eval_remote_src(call.data, engine)
+    if (
+        call.j_type != JType.LIST
+        or len(call.data) == 0
+        or call.data[0].j_type not in [JType.STRING, JType.CAT]
+    ):
+        raise JasmineEvalException(
+            "expect a list of a function name and its arguments, or source code"
+        )
+    name = call.data[0].data
+    fn = engine.builtins.get(name, engine.globals.get(name))
+    if isinstance(fn, J) and fn.j_type == JType.FN:
+        fn = fn.data
+    if not isinstance(fn, JFn):
+        raise JasmineEvalException("'%s' is not a function" % name)
+    engine.sources[REMOTE_SOURCE_ID] = (name, "")
+    return eval_fn(fn, engine, Context(dict()), REMOTE_SOURCE_ID, 0, *call.data[1:])
+
+
+def eval_remote_src(src: str, engine: Engine) -> J:
+    engine.sources[REMOTE_SOURCE_ID] = (src, "")
+    return eval_src(src, REMOTE_SOURCE_ID, engine, Context(dict()))
+
+
+# a serialized result, or a serialized error if it fails or is not serializable
+def to_response(
+    engine: Engine, payload_type: int, payload: bytes, lock: AbstractContextManager
+) -> bytes:
+    try:
+        with lock:
+            j = eval_message(payload_type, payload, engine)
+        if not isinstance(j, J):
+            raise JasmineEvalException(
+                "not support to serialize %s" % type(j).__name__
+            )
+        return serialize(j)
+    except Exception as e:
+        return serialize(J(str(e), JType.ERR))
+
+
+class MessageHandler(socketserver.BaseRequestHandler):
+    # a handle of the served engine connected to itself, its caller holds the lock while
+    # waiting for the response
+    def is_own_handle(self) -> bool:
+        for sock in list(self.server.engine.handles.values()):
+            try:
+                if sock.getsockname() == self.client_address:
+                    return True
+            except OSError:
+                continue
+        return False
+
+    def handle(self):
+        engine = self.server.engine
+        while True:
+            try:
+                msg = read_message(self.request, self.server.max_payload)
+            except PayloadTooLarge as e:
+                print(
+                    "%s from %s:%s, close connection" % (e, *self.client_address),
+                    file=sys.stderr,
+                )
+                if e.msg_type == SYNC:
+                    response = serialize(J(str(e), JType.ERR))
+                    write_message(self.request, RESPONSE, SERIALIZED, response)
+                return
+            except ConnectionError:
+                return
+            if msg is None:
+                return
+            msg_type, payload_type, payload = msg
+            if msg_type == SYNC:
+                lock = nullcontext() if self.is_own_handle() else engine.lock
+                response = to_response(engine, payload_type, payload, lock)
+                write_message(self.request, RESPONSE, SERIALIZED, response)
+            elif msg_type == ASYNC:
+                try:
+                    with engine.lock:
+                        eval_message(payload_type, payload, engine)
+                except Exception as e:
+                    print(
+                        "async message from %s:%s failed - %s"
+                        % (*self.client_address, e),
+                        file=sys.stderr,
+                    )
+            else:
+                print(
+                    "unknown message type %s from %s:%s, close connection"
+                    % (msg_type, *self.client_address),
+                    file=sys.stderr,
+                )
+                return
+
+
+class Server(socketserver.ThreadingTCPServer):
+    allow_reuse_address = True
+    daemon_threads = True
+
+    def __init__(
+        self, engine: Engine, host: str, port: int, max_payload: int = MAX_PAYLOAD
+    ) -> None:
+        super().__init__((host, port), MessageHandler)
+        self.engine = engine
+        self.max_payload = max_payload
+
+
+# serves messages in a background thread, messages are evaluated one at a time, port 0
+# picks a free port
+def start_server(
+    engine: Engine, host: str, port: int, max_payload: int = MAX_PAYLOAD
+) -> Server:
+    server = Server(engine, host, port, max_payload)
+    threading.Thread(target=server.serve_forever, daemon=True).start()
+    return server
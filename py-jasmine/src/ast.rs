@@ -37,6 +37,10 @@ pub enum AstType {
     Sql,
     SqlBracket,
     Skip,
+    Join,
+    Explain,
+    Columns,
+    Handle,
 }
 
 #[pymethods]
@@ -65,6 +69,10 @@ impl Ast {
             AstNode::Sql { .. } => AstType::Sql,
             AstNode::SqlBracket(..) => AstType::SqlBracket,
             AstNode::Skip => AstType::Skip,
+            AstNode::Join { .. } => AstType::Join,
+            AstNode::Explain(_) => AstType::Explain,
+            AstNode::Columns { .. } => AstType::Columns,
+            AstNode::Handle { .. } => AstType::Handle,
         };
         ast_type as u8
     }
@@ -388,10 +396,15 @@ impl Ast {
     pub fn sql(&self) -> PyResult<AstSql> {
         if let AstNode::Sql {
             op,
+            distinct,
+            withs,
             from,
+            joins,
             filters,
             groups,
             ops,
+            reshapes,
+            post_filters,
             sorts,
             take,
             source_id,
@@ -400,10 +413,15 @@ impl Ast {
         {
             Ok(AstSql {
                 op: op.to_string(),
+                distinct: *distinct,
+                withs: withs.into_iter().map(|n| Ast(n.clone())).collect(),
                 from_df: Ast(*from.clone()),
+                joins: joins.into_iter().map(|n| Ast(n.clone())).collect(),
                 filters: filters.into_iter().map(|n| Ast(n.clone())).collect(),
                 groups: groups.into_iter().map(|n| Ast(n.clone())).collect(),
                 ops: ops.into_iter().map(|n| Ast(n.clone())).collect(),
+                reshapes: reshapes.into_iter().map(|n| Ast(n.clone())).collect(),
+                post_filters: post_filters.into_iter().map(|n| Ast(n.clone())).collect(),
                 sorts: sorts.into_iter().map(|n| Ast(n.clone())).collect(),
                 take: Ast(*take.clone()),
                 source_id: *source_id,
@@ -432,6 +450,91 @@ impl Ast {
         }
     }
 
+    pub fn join(&self) -> PyResult<AstJoin> {
+        if let AstNode::Join {
+            how,
+            right,
+            keys,
+            start,
+            source_id,
+        } = &self.0
+        {
+            Ok(AstJoin {
+                how: how.to_string(),
+                right: Ast(*right.clone()),
+                keys: keys.into_iter().map(|n| Ast(n.clone())).collect(),
+                start: *start,
+                source_id: *source_id,
+            })
+        } else {
+            Err(PyJasmineErr::new_err(format!(
+                "failed to refer {0} from {1}",
+                "ast join",
+                self.get_ast_type()
+            )))
+        }
+    }
+
+    pub fn columns(&self) -> PyResult<AstColumns> {
+        if let AstNode::Columns {
+            exp,
+            exclude,
+            by_type,
+            start,
+            source_id,
+        } = &self.0
+        {
+            Ok(AstColumns {
+                exp: Ast(*exp.clone()),
+                exclude: *exclude,
+                by_type: *by_type,
+                start: *start,
+                source_id: *source_id,
+            })
+        } else {
+            Err(PyJasmineErr::new_err(format!(
+                "failed to refer {0} from {1}",
+                "ast columns",
+                self.get_ast_type()
+            )))
+        }
+    }
+
+    pub fn handle(&self) -> PyResult<AstHandle> {
+        if let AstNode::Handle {
+            exp,
+            start,
+            source_id,
+        } = &self.0
+        {
+            Ok(AstHandle {
+                exp: Ast(*exp.clone()),
+                start: *start,
+                source_id: *source_id,
+            })
+        } else {
+            Err(PyJasmineErr::new_err(format!(
+                "failed to refer {0} from {1}",
+                "ast handle",
+                self.get_ast_type()
+            )))
+        }
+    }
+
+    pub fn explain(&self) -> PyResult<AstExplain> {
+        if let AstNode::Explain(node) = &self.0 {
+            Ok(AstExplain {
+                exp: Ast(*node.clone()),
+            })
+        } else {
+            Err(PyJasmineErr::new_err(format!(
+                "failed to refer {0} from {1}",
+                "ast explain",
+                self.get_ast_type()
+            )))
+        }
+    }
+
     pub fn skip(&self) -> PyResult<AstSkip> {
         if let AstNode::Skip = &self.0 {
             Ok(AstSkip {})
@@ -567,10 +670,15 @@ pub struct AstSeries {
 #[pyclass(get_all)]
 pub struct AstSql {
     op: String,
+    distinct: bool,
+    withs: Vec<Ast>,
     from_df: Ast,
+    joins: Vec<Ast>,
     filters: Vec<Ast>,
     groups: Vec<Ast>,
     ops: Vec<Ast>,
+    reshapes: Vec<Ast>,
+    post_filters: Vec<Ast>,
     sorts: Vec<Ast>,
     take: Ast,
     source_id: usize,
@@ -581,3 +689,33 @@ pub struct AstSql {
 pub struct AstSqlBracket {
     exps: Vec<Ast>,
 }
+
+#[pyclass(get_all)]
+pub struct AstJoin {
+    how: String,
+    right: Ast,
+    keys: Vec<Ast>,
+    start: usize,
+    source_id: usize,
+}
+
+#[pyclass(get_all)]
+pub struct AstExplain {
+    exp: Ast,
+}
+
+#[pyclass(get_all)]
+pub struct AstColumns {
+    exp: Ast,
+    exclude: bool,
+    by_type: bool,
+    start: usize,
+    source_id: usize,
+}
+
+#[pyclass(get_all)]
+pub struct AstHandle {
+    exp: Ast,
+    start: usize,
+    source_id: usize,
+}
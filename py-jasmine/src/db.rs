@@ -0,0 +1,205 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use jasmine::db::format::TableFormat;
+use jasmine::db::manifest;
+use jasmine::db::parted::{Parted, PartitionUnit};
+use jasmine::db::scheme::PartitionScheme;
+use jasmine::db::storage::{LocalStorage, Storage};
+use jasmine::db::{loader, sym, verify, writer};
+use jasmine::errors::JError;
+use jasmine::j::J;
+use polars::prelude::{Column, DataFrame};
+use pyo3::{pyfunction, PyRef};
+use pyo3_polars::{PyDataFrame, PyLazyFrame};
+
+use crate::error::JasmineErr;
+use crate::j::JObj;
+
+// databases of python are on the local filesystem
+fn local() -> Arc<dyn Storage> {
+    Arc::new(LocalStorage)
+}
+
+// unit: single|year|month|date|int|hash, series and buckets are only for hash partitions,
+// format: parquet|ipc|ipc_lz4, returns paths of written partitions
+#[pyfunction]
+#[allow(clippy::too_many_arguments)]
+pub fn write_partition(
+    db_path: &str,
+    table: &str,
+    unit: &str,
+    partition: Option<i64>,
+    series: &str,
+    buckets: i64,
+    format: &str,
+    df: PyDataFrame,
+    sort_series: Vec<String>,
+    rechunk: bool,
+    overwrite: bool,
+) -> Result<Vec<String>, JasmineErr> {
+    let unit = PartitionUnit::from_name(unit)
+        .ok_or(JError::Err(format!("unknown partition unit '{}'", unit)))?;
+    let format = TableFormat::from_name(format)
+        .ok_or(JError::Err(format!("unknown table format '{}'", format)))?;
+    let paths = if unit == PartitionUnit::Hash {
+        writer::write_hashed(
+            &local(),
+            Path::new(db_path),
+            table,
+            &PartitionScheme::hash(series, buckets)?.with_format(format),
+            &df.0,
+            &sort_series,
+            rechunk,
+            overwrite,
+        )?
+    } else {
+        vec![writer::write_partition(
+            &local(),
+            Path::new(db_path),
+            table,
+            &PartitionScheme::new(unit).with_format(format),
+            partition,
+            &df.0,
+            &sort_series,
+            rechunk,
+            overwrite,
+        )?]
+    };
+    Ok(paths
+        .iter()
+        .map(|path| path.to_string_lossy().to_string())
+        .collect())
+}
+
+// bucket of a value of the hashed series, same as buckets written by 'write_partition'
+#[pyfunction]
+pub fn hash_bucket(value: &str, buckets: i64) -> Result<i64, JasmineErr> {
+    Ok(PartitionScheme::hash("", buckets)?.bucket(value))
+}
+
+#[pyfunction]
+pub fn load_db(db_path: &str) -> Result<Vec<(String, JObj)>, JasmineErr> {
+    Ok(loader::load_db(&local(), Path::new(db_path))?
+        .into_iter()
+        .map(|(name, j)| (name, JObj::new(j)))
+        .collect())
+}
+
+// pins a version of a table, the latest if none, its chunk files are kept until the
+// returned table and its copies are dropped
+#[pyfunction]
+#[pyo3(signature = (table_path, version=None))]
+pub fn pin_table(table_path: &str, version: Option<u64>) -> Result<JObj, JasmineErr> {
+    let storage = local();
+    let path = Path::new(table_path);
+    let parted = match version {
+        Some(version) => loader::load_snapshot(&storage, path, version)?,
+        None => loader::load_table(&storage, path)?
+            .ok_or(JError::Err(format!("not a table '{}'", table_path)))?
+            .pin()?,
+    };
+    Ok(JObj::new(J::Parted(parted)))
+}
+
+// the version of a pinned table, its (partition, chunk) in scan order and its symbol
+// dictionary
+#[pyfunction]
+pub fn read_pin(table: PyRef<JObj>) -> Result<(u64, Vec<(i64, usize)>, String), JasmineErr> {
+    let manifest = &to_parted(&table)?
+        .pin
+        .as_ref()
+        .ok_or(JError::Err("not a pinned table".to_owned()))?
+        .manifest;
+    Ok((
+        manifest.version,
+        manifest.chunks.clone(),
+        manifest.sym.clone(),
+    ))
+}
+
+// scans a table, chunks in order of a pinned table, enumerated series are decoded as
+// categorical series
+#[pyfunction]
+#[pyo3(signature = (table, chunks=None))]
+pub fn scan_table(
+    table: PyRef<JObj>,
+    chunks: Option<Vec<(i64, usize)>>,
+) -> Result<PyLazyFrame, JasmineErr> {
+    let parted = to_parted(&table)?;
+    let lf = match chunks {
+        Some(chunks) => parted.scan_chunks(&chunks)?,
+        None => parted.scan(None)?,
+    };
+    Ok(PyLazyFrame(lf))
+}
+
+fn to_parted(table: &JObj) -> Result<&Parted, JError> {
+    match table.j() {
+        J::Parted(parted) => Ok(parted),
+        j => Err(JError::Err(format!(
+            "expected a table, got {}",
+            j.get_type_name()
+        ))),
+    }
+}
+
+// symbols of a dictionary of a database in order of their codes
+#[pyfunction]
+pub fn read_syms(db_path: &str, sym: &str) -> Result<Vec<String>, JasmineErr> {
+    Ok(sym::SymDict::load(&LocalStorage, Path::new(db_path), sym)?
+        .syms()
+        .to_vec())
+}
+
+// removes symbols not referred by any partition, returns the number of removed symbols
+#[pyfunction]
+pub fn compact_syms(db_path: &str) -> Result<usize, JasmineErr> {
+    Ok(sym::compact_syms(&local(), Path::new(db_path))?)
+}
+
+// issues of a database as a dataframe of table, path and issue, empty if none
+#[pyfunction]
+pub fn verify_db(db_path: &str) -> Result<PyDataFrame, JasmineErr> {
+    let issues = verify::verify_db(&local(), Path::new(db_path))?;
+    let df = DataFrame::new(vec![
+        Column::new(
+            "table".into(),
+            issues.iter().map(|i| i.table.as_str()).collect::<Vec<_>>(),
+        ),
+        Column::new(
+            "path".into(),
+            issues
+                .iter()
+                .map(|i| i.path.to_string_lossy().to_string())
+                .collect::<Vec<_>>(),
+        ),
+        Column::new(
+            "issue".into(),
+            issues
+                .iter()
+                .map(|i| i.message.as_str())
+                .collect::<Vec<_>>(),
+        ),
+    ])
+    .map_err(JError::from)?;
+    Ok(PyDataFrame(df))
+}
+
+// merges chunks of each partition into one sorted file, returns paths of merged partitions
+#[pyfunction]
+pub fn compact_db(db_path: &str) -> Result<Vec<String>, JasmineErr> {
+    Ok(verify::compact_db(&local(), Path::new(db_path))?
+        .iter()
+        .map(|path| path.to_string_lossy().to_string())
+        .collect())
+}
+
+// kept versions of a partitioned table, empty if written before versions were recorded
+#[pyfunction]
+pub fn list_versions(table_path: &str) -> Result<Vec<u64>, JasmineErr> {
+    Ok(manifest::list_versions(
+        &LocalStorage,
+        Path::new(table_path),
+    )?)
+}
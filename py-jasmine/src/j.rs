@@ -109,6 +109,42 @@ impl JObj {
         }
     }
 
+    // items of a list, as JObj
+    pub fn as_list(&self) -> PyResult<Vec<JObj>> {
+        match &self.j {
+            J::MixedList(l) => Ok(l.iter().map(|j| JObj::new(j.clone())).collect()),
+            _ => Err(PyJasmineErr::new_err(format!(
+                "expect 'list', got {}",
+                self.j.get_type_name()
+            ))),
+        }
+    }
+
+    // message of an err, as 'as_py' raises it
+    pub fn as_err(&self) -> PyResult<String> {
+        match &self.j {
+            J::Err(e) => Ok(e.clone()),
+            _ => Err(PyJasmineErr::new_err(format!(
+                "expect 'err', got {}",
+                self.j.get_type_name()
+            ))),
+        }
+    }
+
+    // (key, value) of a dict in order, values as JObj
+    pub fn as_dict(&self) -> PyResult<Vec<(String, JObj)>> {
+        match &self.j {
+            J::Dict(dict) => Ok(dict
+                .iter()
+                .map(|(k, v)| (k.clone(), JObj::new(v.clone())))
+                .collect()),
+            _ => Err(PyJasmineErr::new_err(format!(
+                "expect 'dict', got {}",
+                self.j.get_type_name()
+            ))),
+        }
+    }
+
     pub fn as_py(&self, py: Python<'_>) -> PyResult<PyObject> {
         match &self.j {
             J::Boolean(v) => Ok(v.into_py(py)),
@@ -142,11 +178,25 @@ impl JObj {
             }
             J::DataFrame(data_frame) => Ok(PyDataFrame(data_frame.clone()).into_py(py)),
             J::Err(v) => Err(PyJasmineErr::new_err(v.to_string()).into()),
+            J::Parted(parted) => Ok((
+                parted.path.to_string_lossy().to_string(),
+                parted.scheme.unit.name(),
+                parted.scheme.series.clone(),
+                parted.scheme.buckets,
+                parted.scheme.enums.clone(),
+                parted.partitions.clone(),
+                parted.version,
+            )
+                .into_py(py)),
         }
     }
 }
 
 impl JObj {
+    pub fn j(&self) -> &J {
+        &self.j
+    }
+
     pub fn new(j: J) -> Self {
         let j_type = match j {
             J::None => JType::None,
@@ -166,6 +216,7 @@ impl JObj {
             J::Dict(_) => JType::Dict,
             J::DataFrame(_) => JType::DataFrame,
             J::Err(_) => JType::Err,
+            J::Parted(_) => JType::Parted,
         };
         Self {
             j,
@@ -193,4 +244,6 @@ pub enum JType {
     Dict,
     DataFrame,
     Err,
+    // 17-19 are python only types
+    Parted = 20,
 }
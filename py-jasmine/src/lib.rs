@@ -1,15 +1,22 @@
+pub mod db;
 pub mod error;
 pub mod j;
 pub mod parse;
+pub mod serialize;
+use db::{
+    compact_db, compact_syms, hash_bucket, list_versions, load_db, pin_table, read_pin, read_syms,
+    scan_table, verify_db, write_partition,
+};
 use error::{PyJasmineErr, PyJasmineParseErr};
 use j::JObj;
 use jasmine::trace;
 use parse::parse_source_code;
+use serialize::{deserialize, serialize};
 pub mod ast;
 use ast::{
-    Ast, AstAssign, AstBinOp, AstCall, AstDataFrame, AstDict, AstFn, AstId, AstIf, AstIndexAssign,
-    AstList, AstMatrix, AstOp, AstRaise, AstReturn, AstSeries, AstSkip, AstSql, AstSqlBracket,
-    AstTry, AstUnaryOp, AstWhile,
+    Ast, AstAssign, AstBinOp, AstCall, AstColumns, AstDataFrame, AstDict, AstExplain, AstFn,
+    AstHandle, AstId, AstIf, AstIndexAssign, AstJoin, AstList, AstMatrix, AstOp, AstRaise,
+    AstReturn, AstSeries, AstSkip, AstSql, AstSqlBracket, AstTry, AstUnaryOp, AstWhile,
 };
 use pyo3::prelude::*;
 
@@ -49,7 +56,24 @@ fn jasminum(py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<AstSql>()?;
     m.add_class::<AstSqlBracket>()?;
     m.add_class::<AstSkip>()?;
+    m.add_class::<AstJoin>()?;
+    m.add_class::<AstExplain>()?;
+    m.add_class::<AstColumns>()?;
+    m.add_class::<AstHandle>()?;
     m.add_function(wrap_pyfunction!(parse_source_code, m)?)?;
     m.add_function(wrap_pyfunction!(print_trace, m)?)?;
+    m.add_function(wrap_pyfunction!(write_partition, m)?)?;
+    m.add_function(wrap_pyfunction!(hash_bucket, m)?)?;
+    m.add_function(wrap_pyfunction!(load_db, m)?)?;
+    m.add_function(wrap_pyfunction!(pin_table, m)?)?;
+    m.add_function(wrap_pyfunction!(read_pin, m)?)?;
+    m.add_function(wrap_pyfunction!(scan_table, m)?)?;
+    m.add_function(wrap_pyfunction!(read_syms, m)?)?;
+    m.add_function(wrap_pyfunction!(compact_syms, m)?)?;
+    m.add_function(wrap_pyfunction!(verify_db, m)?)?;
+    m.add_function(wrap_pyfunction!(compact_db, m)?)?;
+    m.add_function(wrap_pyfunction!(list_versions, m)?)?;
+    m.add_function(wrap_pyfunction!(serialize, m)?)?;
+    m.add_function(wrap_pyfunction!(deserialize, m)?)?;
     Ok(())
 }
@@ -0,0 +1,76 @@
+use chrono::{Datelike, NaiveDate};
+use indexmap::IndexMap;
+use jasmine::{j::J, UNIX_EPOCH_DAY};
+use numpy::PyReadonlyArray2;
+use pyo3::{
+    pyfunction,
+    types::{PyAnyMethods, PyBytes, PyDict, PyDictMethods},
+    Bound, PyAny, PyRef, PyResult, Python,
+};
+use pyo3_polars::{PyDataFrame, PySeries};
+
+use crate::error::PyJasmineErr;
+use crate::j::{JObj, JType};
+
+// python J -> J, by its 'j_type' and 'data'
+fn to_j(j: &Bound<'_, PyAny>) -> PyResult<J> {
+    let j_type: u8 = j.getattr("j_type")?.getattr("value")?.extract()?;
+    let data = j.getattr("data")?;
+    match j_type {
+        t if t == JType::None as u8 => Ok(J::None),
+        t if t == JType::Boolean as u8 => Ok(J::Boolean(data.extract()?)),
+        t if t == JType::I64 as u8 => Ok(J::I64(data.extract()?)),
+        t if t == JType::Date as u8 => {
+            let date: NaiveDate = data.extract()?;
+            Ok(J::Date(date.num_days_from_ce() - UNIX_EPOCH_DAY))
+        }
+        t if t == JType::Time as u8 => Ok(J::Time(data.extract()?)),
+        t if t == JType::Datetime as u8 || t == JType::Timestamp as u8 => {
+            Ok(data.extract::<PyRef<JObj>>()?.j().clone())
+        }
+        t if t == JType::Duration as u8 => Ok(J::Duration(data.extract()?)),
+        t if t == JType::F64 as u8 => Ok(J::F64(data.extract()?)),
+        t if t == JType::String as u8 => Ok(J::String(data.extract()?)),
+        t if t == JType::Cat as u8 => Ok(J::Cat(data.extract()?)),
+        t if t == JType::Series as u8 => Ok(J::Series(data.extract::<PySeries>()?.0)),
+        t if t == JType::Matrix as u8 => Ok(J::Matrix(
+            data.extract::<PyReadonlyArray2<f64>>()?
+                .as_array()
+                .to_shared(),
+        )),
+        t if t == JType::List as u8 => Ok(J::MixedList(
+            data.iter()?
+                .map(|item| to_j(&item?))
+                .collect::<PyResult<Vec<_>>>()?,
+        )),
+        t if t == JType::Dict as u8 => {
+            let mut dict = IndexMap::new();
+            for (k, v) in data.downcast::<PyDict>()?.iter() {
+                dict.insert(k.extract()?, to_j(&v)?);
+            }
+            Ok(J::Dict(dict))
+        }
+        t if t == JType::DataFrame as u8 => Ok(J::DataFrame(data.extract::<PyDataFrame>()?.0)),
+        t if t == JType::Err as u8 => Ok(J::Err(data.extract()?)),
+        _ => Err(PyJasmineErr::new_err(format!(
+            "not support to serialize {}",
+            j.getattr("j_type")?.getattr("name")?
+        ))),
+    }
+}
+
+// serialized python J by type codes of J, see 'jasmine::serialize'
+#[pyfunction]
+pub fn serialize<'py>(py: Python<'py>, j: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyBytes>> {
+    let bytes = to_j(j)?
+        .serialize()
+        .map_err(|e| PyJasmineErr::new_err(e.to_string()))?;
+    Ok(PyBytes::new_bound(py, &bytes))
+}
+
+#[pyfunction]
+pub fn deserialize(bytes: &[u8]) -> PyResult<JObj> {
+    J::deserialize(bytes)
+        .map(JObj::new)
+        .map_err(|e| PyJasmineErr::new_err(e.to_string()))
+}
@@ -6,6 +6,7 @@ import pytest
 from jasminum.context import Context
 from jasminum.engine import Engine
 from jasminum.eval import eval_src
+from jasminum.exceptions import JasmineEvalException
 from jasminum.j import J, JType
 
 
@@ -16,6 +17,19 @@ from jasminum.j import J, JType
         ("2024-10-23+1D00:12:34.5", J(date(2024, 10, 24))),
         ("2024-10-23D+0D00:12:34.5", J.from_nanos(1729642354500000000, "UTC")),
         ("[,,]", J(pl.Series("", [None, None, None]))),
+        ("5 ~xbar 13", J(10, JType.INT)),
+        ("xbar(1h, 2024-12-10D10:35:00)", J.from_nanos(1733824800000000000, "UTC")),
+        ("xbar(7D, 2024-12-10)", J(date(2024, 12, 5))),
+        ("2 ~in [1, 2]", J(True, JType.BOOLEAN)),
+        ('`a` ~in ["a", "b"]', J(True, JType.BOOLEAN)),
+        ("3 ~between [1, 2]", J(False, JType.BOOLEAN)),
+        ('like("abc", "a?c")', J(True, JType.BOOLEAN)),
+        ('like("bcd", "[a-c]*")', J(True, JType.BOOLEAN)),
+        ('like("-", "[a-c]")', J(False, JType.BOOLEAN)),
+        ('like("a[", "a[")', J(True, JType.BOOLEAN)),
+        ('match(`abc`, "^b")', J(False, JType.BOOLEAN)),
+        ('match(`abc`, "b")', J(True, JType.BOOLEAN)),
+        ('match(`abc`, "^b$")', J(False, JType.BOOLEAN)),
         (
             "df[series1 = [0i32,,], series2 = [none, 2.0, 3.0]]",
             J(
@@ -36,3 +50,9 @@ def test_simple_src(src, expect):
         res.data.equals(expect.data)
     else:
         assert res == expect
+
+
+def test_assert_type():
+    J(1, JType.INT).assert_type(JType.INT)
+    with pytest.raises(JasmineEvalException, match="expect 'FLOAT'"):
+        J(1, JType.INT).assert_type(JType.FLOAT)
@@ -0,0 +1,148 @@
+import socket
+
+import polars as pl
+import pytest
+
+from jasminum.ast import deserialize, serialize
+from jasminum.context import Context
+from jasminum.engine import Engine
+from jasminum.eval import eval_src
+from jasminum.exceptions import JasmineEvalException
+from jasminum.j import J, JType
+from jasminum.server import (
+    ASYNC,
+    RESPONSE,
+    SERIALIZED,
+    SOURCE,
+    SYNC,
+    PayloadTooLarge,
+    call,
+    read_message,
+    start_server,
+    write_message,
+)
+
+
+@pytest.fixture
+def conn():
+    server = start_server(Engine(), "127.0.0.1", 0)
+    sock = socket.create_connection(server.server_address)
+    yield sock
+    sock.close()
+    server.shutdown()
+    server.server_close()
+
+
+def request(sock: socket.socket, payload_type: int, payload: bytes) -> J:
+    write_message(sock, SYNC, payload_type, payload)
+    msg_type, payload_type, payload = read_message(sock)
+    assert (msg_type, payload_type) == (RESPONSE, SERIALIZED)
+    return J(deserialize(payload))
+
+
+def test_serialize_j():
+    for j in [
+        J(None, JType.NONE),
+        J(1, JType.INT),
+        J("a", JType.CAT),
+        J(pl.Series("qty", [1, 2])),
+        J(pl.DataFrame({"sym": ["a"], "qty": [1]})),
+        J([J(1.5, JType.FLOAT), J("s", JType.STRING)], JType.LIST),
+    ]:
+        assert str(J(deserialize(serialize(j)))) == str(j)
+
+
+def test_server_source(conn):
+    j = request(conn, SOURCE, b"t = df[sym = [`a`, `b`], qty = [1, 2]]; t")
+    assert j.j_type == JType.DATAFRAME
+    assert j.data.shape == (2, 2)
+    j = request(conn, SOURCE, b"y + 1")
+    assert j.j_type == JType.ERR
+
+
+def test_server_call(conn):
+    call = J([J("+", JType.CAT), J(1, JType.INT), J(2, JType.INT)], JType.LIST)
+    assert request(conn, SERIALIZED, serialize(call)).data == 3
+    call = J([J("unknown", JType.CAT)], JType.LIST)
+    j = request(conn, SERIALIZED, serialize(call))
+    assert j.j_type == JType.ERR
+    assert j.data == "'unknown' is not a function"
+
+
+def test_server_async(conn):
+    write_message(conn, ASYNC, SOURCE, b"x = 7")
+    write_message(conn, ASYNC, SOURCE, b"y + 1")
+    assert request(conn, SOURCE, b"x").data == 7
+
+
+def test_server_max_payload():
+    server = start_server(Engine(), "127.0.0.1", 0, 16)
+    sock = socket.create_connection(server.server_address)
+    j = request(sock, SOURCE, b"1 + 1")
+    assert j.data == 2
+    # the header claims 4 GiB, the server rejects it without waiting for the payload
+    sock.sendall(bytes([SYNC, SOURCE, 0, 0, 255, 255, 255, 255]))
+    j = J(deserialize(read_message(sock)[2]))
+    assert j.j_type == JType.ERR
+    assert "exceeds max payload 16 bytes" in j.data
+    assert read_message(sock) is None
+    sock.close()
+    server.shutdown()
+    server.server_close()
+    left, right = socket.socketpair()
+    write_message(left, SYNC, SOURCE, b"x" * 17)
+    with pytest.raises(PayloadTooLarge):
+        read_message(right, 16)
+    # responses are limited by the caller
+    write_message(right, RESPONSE, SERIALIZED, b"x" * 17)
+    with pytest.raises(PayloadTooLarge):
+        call(left, J(1, JType.INT), 16)
+    left.close()
+    right.close()
+
+
+def test_handle(conn):
+    engine = Engine()
+
+    def eval_j(src: str) -> J:
+        engine.sources[0] = (src, "")
+        return eval_src(src, 0, engine, Context(dict()))
+
+    port = conn.getpeername()[1]
+    c = eval_j("c = h[`:127.0.0.1:%s`]" % port)
+    assert c.j_type == JType.INT
+    assert c.data == 1
+    assert c.data in engine.handles
+    j = eval_j('h[c]("t = df[sym = [`a`, `b`], qty = [1, 2]]; from t select {qty}")')
+    assert j.data.columns == ["qty"]
+    assert eval_j("h[c](`+, 1, 2)").data == 3
+    # local fns are not sent, remote fns are referred by name
+    with pytest.raises(JasmineEvalException, match="a fn name as cat"):
+        eval_j("h[c](xbar, 5, 7)")
+    with pytest.raises(JasmineEvalException, match="got INT"):
+        eval_j("h[c](1, 2)")
+    with pytest.raises(JasmineEvalException, match="'f' is not a function"):
+        eval_j("h[c](`f, 1)")
+    with pytest.raises(JasmineEvalException, match="'y' is not defined"):
+        eval_j('h[c]("y + 1")')
+    eval_j("hclose c")
+    with pytest.raises(JasmineEvalException, match="is not open"):
+        eval_j('h[c]("1")')
+
+
+def test_own_handle():
+    engine = Engine()
+    server = start_server(engine, "127.0.0.1", 0)
+
+    def eval_j(src: str) -> J:
+        engine.sources[0] = (src, "")
+        return eval_src(src, 0, engine, Context(dict()))
+
+    # the console holds the lock while calling its own server
+    with engine.lock:
+        eval_j("c = h[`:127.0.0.1:%s`]" % server.server_address[1])
+        assert eval_j('h[c]("x = 7; x + 1")').data == 8
+    assert eval_j("x").data == 7
+    eval_j("hclose c")
+    server.shutdown()
+    server.server_close()
@@ -0,0 +1,448 @@
+import polars as pl
+import pytest
+from polars.testing import assert_frame_equal
+
+from jasminum.context import Context
+from jasminum.engine import Engine
+from jasminum.eval import eval_src
+from jasminum.exceptions import JasmineEvalException
+
+
+def eval_df(src: str, engine: Engine) -> pl.DataFrame:
+    engine.sources[0] = (src, "")
+    return eval_src(src, 0, engine, Context(dict())).data
+
+
+def test_sql_join():
+    engine = Engine()
+    eval_df(
+        "t1 = df[id = [1, 2, 3], qty = [10, 20, 30]]; t2 = df[id = [1, 3], qty = [1.0, 3.0]]",
+        engine,
+    )
+    assert_frame_equal(
+        eval_df("from t1 left join t2 on {id}", engine),
+        pl.DataFrame(
+            [
+                pl.Series("id", [1, 2, 3]),
+                pl.Series("qty", [10, 20, 30]),
+                pl.Series("qty_t2", [1.0, None, 3.0]),
+            ]
+        ),
+    )
+    assert_frame_equal(
+        eval_df("from t1 anti join t2 on {id}", engine),
+        pl.DataFrame([pl.Series("id", [2]), pl.Series("qty", [20])]),
+    )
+    assert eval_df("from t1 union join t2", engine).shape == (5, 2)
+
+
+def test_as_of_join():
+    engine = Engine()
+    eval_df(
+        "trade = df[id = [1, 2, 1], time = [3, 5, 8], qty = [10, 20, 30]];"
+        "quote = df[id = [1, 2, 1, 2], time = [1, 2, 4, 6], bid = [1.0, 2.0, 3.0, 4.0]]",
+        engine,
+    )
+    assert_frame_equal(
+        eval_df("aj([`id`, `time`], trade, quote, none, none)", engine),
+        pl.DataFrame(
+            [
+                pl.Series("id", [1, 2, 1]),
+                pl.Series("time", [3, 5, 8]),
+                pl.Series("qty", [10, 20, 30]),
+                pl.Series("bid", [1.0, 2.0, 3.0]),
+            ]
+        ),
+    )
+    assert_frame_equal(
+        eval_df("wj([-3, 0], [`id`, `time`], trade, quote, {bid: `max`})", engine),
+        pl.DataFrame(
+            [
+                pl.Series("id", [1, 2, 1]),
+                pl.Series("time", [3, 5, 8]),
+                pl.Series("qty", [10, 20, 30]),
+                pl.Series("bid", [1.0, 2.0, None]),
+            ]
+        ),
+    )
+
+
+def test_window_join_equal_times():
+    engine = Engine()
+    eval_df(
+        "trade = df[time = [2, 5, 9]];"
+        "quote = df[time = [2, 2, 4, 5, 5], bid = [1.0, 2.0, 3.0, 4.0, 5.0],"
+        " size = [1, 2, 3, 4, 5]]",
+        engine,
+    )
+    assert_frame_equal(
+        eval_df("wj([0, 3], [`time`], trade, quote, {bid: `count`, size: `sum`})", engine),
+        pl.DataFrame(
+            [
+                pl.Series("time", [2, 5, 9]),
+                pl.Series("bid", [5, 2, 0], dtype=pl.UInt32),
+                pl.Series("size", [15, 9, None]),
+            ]
+        ),
+    )
+
+
+def test_sql_xbar():
+    engine = Engine()
+    eval_df("t = df[time = [1, 4, 6, 11], qty = [1, 2, 3, 4]]", engine)
+    assert_frame_equal(
+        eval_df("from t group {time = 5 ~xbar time} select {qty = sum qty}", engine),
+        pl.DataFrame([pl.Series("time", [0, 5, 10]), pl.Series("qty", [3, 3, 4])]),
+    )
+
+
+def test_sql_exec_distinct():
+    engine = Engine()
+    eval_df("t = df[id = [1, 1, 2], qty = [1, 1, 3]]", engine)
+    assert_frame_equal(
+        eval_df("from t select distinct {}", engine),
+        pl.DataFrame([pl.Series("id", [1, 2]), pl.Series("qty", [1, 3])]),
+    )
+    assert eval_df("from t exec distinct {id}", engine).to_list() == [1, 2]
+    assert eval_df("from t exec {id, qty}", engine)["qty"].data.to_list() == [1, 1, 3]
+
+
+def test_sql_predicate():
+    engine = Engine()
+    eval_df("t = df[sym = [`aa`, `ab`, `b`], qty = [1, 2, 3]]", engine)
+    assert eval_df("from t filter {sym ~in [`aa`, `b`]} exec {qty}", engine).to_list() == [
+        1,
+        3,
+    ]
+    assert eval_df('from t filter {sym ~in "b"} exec {qty}', engine).to_list() == [3]
+    assert eval_df("from t filter {qty ~between [2, 3]} exec {qty}", engine).to_list() == [
+        2,
+        3,
+    ]
+    assert eval_df('from t filter {sym ~like "a*"} exec {qty}', engine).to_list() == [1, 2]
+    assert eval_df('from t filter {sym ~like "[a-c]b"} exec {qty}', engine).to_list() == [2]
+    assert eval_df('from t filter {sym ~like "a["} exec {qty}', engine).to_list() == []
+    assert eval_df('from t filter {sym ~match "b$"} exec {qty}', engine).to_list() == [2, 3]
+
+
+def test_sql_post_filter():
+    engine = Engine()
+    eval_df("t = df[id = [1, 1, 2, 3], qty = [1, 2, 3, 4]]", engine)
+    assert_frame_equal(
+        eval_df(
+            "from t filter {qty > 1} group {id} select {qty = sum qty} filter {qty > 3}",
+            engine,
+        ),
+        pl.DataFrame([pl.Series("id", [3]), pl.Series("qty", [4])]),
+    )
+
+
+def test_sql_explain():
+    engine = Engine()
+    eval_df("t = df[id = [1, 2, 3], qty = [1, 2, 3]]", engine)
+    j = eval_df("explain from t filter {qty > 1} select {id}", engine)
+    assert "FILTER" in j["plan"].data
+    assert j["chunks"].data.to_list() == []
+    assert j["columns"].data.to_list() == ["id"]
+
+
+def test_sql_partition_pruning(tmp_path):
+    engine = Engine()
+    eval_df("t = df[qty = [1, 2]]", engine)
+    for day in ["2024-01-02", "2024-01-03", "2024-01-04"]:
+        eval_df(
+            'wpart("%s", %s, `trade`, t, [], false, true)' % (tmp_path, day), engine
+        )
+    eval_df('load("%s")' % tmp_path, engine)
+    j = eval_df(
+        "explain from trade filter {qty > 1, date >= 2024-01-03, 2024-01-04 > date}",
+        engine,
+    )
+    assert [p.split("/")[-1] for p in j["chunks"].data.to_list()] == [
+        "20240103_0000"
+    ]
+    # all chunks skipped by their metadata
+    j = eval_df("explain from trade filter {date == 2024-01-03, qty > 2}", engine)
+    assert j["chunks"].data.to_list() == []
+    assert eval_df(
+        "from trade filter {date == 2024-01-03, qty > 2}", engine
+    ).shape == (0, 1)
+    assert eval_df("from trade filter {date != 2024-01-03}", engine).shape == (4, 1)
+    assert eval_df(
+        "query(trade, l[l[>, `qty`, 1], l[in, `date`, 2024-01-02]], none, none)",
+        engine,
+    ).to_dicts() == [{"qty": 2}]
+    assert eval_df(
+        "query(trade, l[l[in, `date`, l[2024-01-02, 2024-01-04]]], none, none)",
+        engine,
+    )["qty"].to_list() == [1, 2, 1, 2]
+
+
+def test_sql_reconciled_partitions(tmp_path):
+    engine = Engine()
+    eval_df("t1 = df[qty = [1]]; t2 = df[qty = [2.5], price = [1.5]]", engine)
+    eval_df('wpart("%s", 2024-01-02, `trade`, t1, [], false, true)' % tmp_path, engine)
+    eval_df('wpart("%s", 2024-01-03, `trade`, t2, [], false, true)' % tmp_path, engine)
+    eval_df('load("%s")' % tmp_path, engine)
+    assert_frame_equal(
+        eval_df("from trade filter {date ~between [2024-01-02, 2024-01-03]}", engine),
+        pl.DataFrame(
+            [
+                pl.Series("qty", [1.0, 2.5]),
+                pl.Series("price", [None, 1.5]),
+            ]
+        ),
+    )
+
+
+def test_sql_partition_schemes(tmp_path):
+    engine = Engine()
+    eval_df("t = df[sym = [`a`, `b`, `c`], qty = [1, 2, 3]]", engine)
+    for month in ["2024-01-01", "2024-02-01"]:
+        eval_df(
+            'wpart("%s", {month: %s}, `bar`, t, [], false, true)' % (tmp_path, month),
+            engine,
+        )
+    eval_df(
+        'wpart("%s", {hash: `sym`, buckets: 4}, `trade`, t, [], false, true)'
+        % tmp_path,
+        engine,
+    )
+    eval_df('load("%s")' % tmp_path, engine)
+    assert eval_df("from bar filter {month > 2024-01-01}", engine).shape == (3, 2)
+    j = eval_df("explain from trade filter {sym == `b`}", engine)
+    assert len(j["chunks"].data) == 1
+    assert eval_df("from trade filter {sym == `b`}", engine).to_dicts() == [
+        {"sym": "b", "qty": 2}
+    ]
+
+
+def test_sql_enumerated_partitions(tmp_path):
+    engine = Engine()
+    eval_df("t1 = df[sym = [`b`, `a`], qty = [1, 2]]", engine)
+    eval_df("t2 = df[sym = [`c`], qty = [3]]", engine)
+    for day, t in [("2024-01-02", "t1"), ("2024-01-03", "t2")]:
+        eval_df(
+            'wpart("%s", %s, `trade`, %s, [], false, true)' % (tmp_path, day, t), engine
+        )
+    eval_df('load("%s")' % tmp_path, engine)
+    df = eval_df("from trade filter {date >= 2024-01-02} select {sym}", engine)
+    assert df["sym"].dtype == pl.Categorical
+    assert df["sym"].to_list() == ["b", "a", "c"]
+    eval_df('wpart("%s", 2024-01-03, `trade`, t1, [], false, true)' % tmp_path, engine)
+    assert eval_df('compactsym("%s")' % tmp_path, engine) == 1
+    df = eval_df("from trade filter {date >= 2024-01-02} select {sym}", engine)
+    assert df["sym"].to_list() == ["b", "a", "b", "a"]
+
+
+def test_sql_chunk_meta(tmp_path):
+    engine = Engine()
+    eval_df("t1 = df[sym = [`c`, `a`, `b`, `a`], qty = [1, 2, 3, 4]]", engine)
+    eval_df("t2 = df[sym = [`d`], qty = [20]]", engine)
+    eval_df(
+        'wpart("%s", 2024-01-02, `trade`, t1, [`sym`], false, true)' % tmp_path, engine
+    )
+    eval_df('wpart("%s", 2024-01-02, `trade`, t2, [], false, false)' % tmp_path, engine)
+    eval_df('load("%s")' % tmp_path, engine)
+    j = eval_df("explain from trade filter {date == 2024-01-02, qty > 10}", engine)
+    assert [p.split("/")[-1] for p in j["chunks"].data.to_list()] == [
+        "20240102_0001"
+    ]
+    assert eval_df(
+        "from trade filter {date == 2024-01-02, sym == `a`} sort {qty}", engine
+    ).to_dicts() == [{"sym": "a", "qty": 2}, {"sym": "a", "qty": 4}]
+    assert eval_df(
+        "from trade filter {date == 2024-01-02, sym == `e`}", engine
+    ).shape == (0, 2)
+
+
+def test_sql_compact_db(tmp_path):
+    engine = Engine()
+    eval_df("t1 = df[sym = [`c`, `a`], qty = [3, 1]]", engine)
+    eval_df("t2 = df[sym = [`b`], qty = [2]]", engine)
+    for t in ["t1", "t2"]:
+        eval_df(
+            'wpart("%s", 2024-01-02, `trade`, %s, [`qty`], false, false)'
+            % (tmp_path, t),
+            engine,
+        )
+    (tmp_path / "trade" / ".20240102_0002.tmp").write_bytes(b"partial")
+    assert eval_df('verifydb("%s")' % tmp_path, engine)["issue"].to_list() == [
+        "orphan temp file"
+    ]
+    paths = eval_df('compactdb("%s")' % tmp_path, engine)
+    assert [p.split("/")[-1] for p in paths.to_list()] == ["20240102_0002"]
+    assert eval_df('verifydb("%s")' % tmp_path, engine).shape == (0, 3)
+    eval_df('load("%s")' % tmp_path, engine)
+    assert eval_df("from trade", engine)["qty"].to_list() == [1, 2, 3]
+
+
+def test_sql_snapshot(tmp_path):
+    engine = Engine()
+    for qty in [1, 2]:
+        eval_df("t = df[qty = [%s]]" % qty, engine)
+        eval_df(
+            'wpart("%s", 2024-01-02, `trade`, t, [], false, true)' % tmp_path, engine
+        )
+    eval_df('load("%s")' % tmp_path, engine)
+    assert eval_df("versions(trade)", engine).to_list() == [1, 2]
+    eval_df("s = snapshot(trade, 1)", engine)
+    eval_df("t = df[qty = [3]]", engine)
+    eval_df('wpart("%s", 2024-01-02, `trade`, t, [], false, false)' % tmp_path, engine)
+    assert eval_df("from s", engine)["qty"].to_list() == [1]
+    assert eval_df("from trade", engine)["qty"].to_list() == [2, 3]
+
+
+def test_sql_snapshot_over_publishes(tmp_path):
+    engine = Engine()
+    eval_df("t = df[qty = [0]]", engine)
+    eval_df('wpart("%s", 2024-01-02, `trade`, t, [], false, true)' % tmp_path, engine)
+    eval_df('load("%s")' % tmp_path, engine)
+    eval_df("s = snapshot(trade, 1)", engine)
+    for qty in range(1, 11):
+        eval_df("t = df[qty = [%s]]" % qty, engine)
+        eval_df(
+            'wpart("%s", 2024-01-02, `trade`, t, [], false, true)' % tmp_path, engine
+        )
+    assert eval_df("versions(trade)", engine).to_list() == list(range(4, 12))
+    assert eval_df("from s", engine)["qty"].to_list() == [0]
+    assert eval_df('verifydb("%s")' % tmp_path, engine).shape == (0, 3)
+
+
+def test_sql_ipc_table(tmp_path):
+    engine = Engine()
+    eval_df("t = df[sym = [`b`, `a`, `c`], qty = [1, 2, 3]]", engine)
+    eval_df(
+        'wpart("%s", {date: 2024-01-02, format: `ipc`}, `trade`, t, [`qty`], false, true)'
+        % tmp_path,
+        engine,
+    )
+    eval_df(
+        'wpart("%s", {format: `ipc_lz4`}, `ref`, t, [], false, true)' % tmp_path, engine
+    )
+    assert (tmp_path / "trade" / "20240102_0000").read_bytes()[:6] == b"ARROW1"
+    eval_df('load("%s")' % tmp_path, engine)
+    assert eval_df(
+        "from trade filter {date == 2024-01-02, qty > 1}", engine
+    ).to_dicts() == [{"sym": "a", "qty": 2}, {"sym": "c", "qty": 3}]
+    assert eval_df("from ref", engine).shape == (3, 2)
+
+
+def test_functional_query():
+    engine = Engine()
+    eval_df("t = df[sym = [`a`, `a`, `b`], qty = [10, 60, 70]]", engine)
+    assert_frame_equal(
+        eval_df(
+            "query(t, l[l[>, `qty`, 50], l[==, `sym`, l[`a`]]], none, none)", engine
+        ),
+        eval_df("from t filter {qty > 50, sym == `a`}", engine),
+    )
+    assert_frame_equal(
+        eval_df("query(t, l[], {sym: `sym`}, {qty: l[`sum`, `qty`]})", engine),
+        eval_df("from t group {sym} select {qty = sum qty}", engine),
+    )
+    assert eval_df(
+        "query(t, l[l[in, `sym`, l[`a`, `c`]], l[between, `qty`, l[20, 60]]],"
+        " none, none)",
+        engine,
+    ).to_dicts() == [{"sym": "a", "qty": 60}]
+    with pytest.raises(JasmineEvalException, match="takes 1 arguments but 2"):
+        eval_df("query(t, l[], none, {qty: l[`sum`, `qty`, `qty`]})", engine)
+    with pytest.raises(JasmineEvalException, match="not supported in sql"):
+        eval_df("query(t, l[], none, {qty: l[`rmax`, 2, `qty`]})", engine)
+
+
+def test_sql_update_delete():
+    engine = Engine()
+    eval_df("t = df[sym = [`a`, `a`, `b`], qty = [1, 2, 3]]", engine)
+    assert eval_df("from t group {sym} update {qty = sum qty}", engine).to_dicts() == [
+        {"sym": "a", "qty": 3},
+        {"sym": "a", "qty": 3},
+        {"sym": "b", "qty": 3},
+    ]
+    assert eval_df("from t delete {qty}", engine).columns == ["sym"]
+
+
+def test_sql_columns():
+    engine = Engine()
+    eval_df("t = df[sym = [`a`, `b`], qty = [1, 2], price = [1.0, 2.0]]", engine)
+    eval_df("cols = [`sym`, `qty`]", engine)
+    assert eval_df("from t select {c[cols]}", engine).columns == ["sym", "qty"]
+    assert eval_df("from t select {c[!`sym`]}", engine).columns == ["qty", "price"]
+    assert eval_df('from t select {c["^p"]}', engine).columns == ["price"]
+    assert eval_df("from t select {c[$`f64`]}", engine).columns == ["price"]
+
+
+def test_reshape():
+    engine = Engine()
+    eval_df("t = df[sym = [`a`, `a`, `b`], qty = [1, 2, 3]]", engine)
+    assert_frame_equal(
+        eval_df("from ungroup(from t group {sym} select {qty}) filter {qty > 1}", engine),
+        pl.DataFrame(
+            [pl.Series("sym", ["a", "b"], pl.Categorical), pl.Series("qty", [2, 3])]
+        ),
+    )
+    eval_df("t2 = df[d = [1, 1, 2], sym = [`a`, `b`, `a`], qty = [1, 2, 3]]", engine)
+    assert eval_df("pivot(t2, `sym`, `d`, `qty`, `sum`)", engine).to_dicts() == [
+        {"d": 1, "a": 1, "b": 2},
+        {"d": 2, "a": 3, "b": None},
+    ]
+    assert eval_df("unpivot(t, `sym`, none)", engine).columns == [
+        "sym",
+        "variable",
+        "value",
+    ]
+    assert_frame_equal(
+        eval_df("from t group {sym} select {qty} ungroup filter {qty > 1}", engine),
+        pl.DataFrame(
+            [pl.Series("sym", ["a", "b"], pl.Categorical), pl.Series("qty", [2, 3])]
+        ),
+    )
+    assert eval_df(
+        "from t2 filter {qty > 1} pivot(`sym`, `d`, `qty`, `sum`) sort {d}", engine
+    ).to_dicts() == [{"d": 1, "b": 2, "a": None}, {"d": 2, "b": None, "a": 3}]
+    assert eval_df("from t unpivot(`sym`, none)", engine).columns == [
+        "sym",
+        "variable",
+        "value",
+    ]
+    with pytest.raises(JasmineEvalException, match="other than the dataframe"):
+        eval_df("from t2 pivot(`sym`, `d`)", engine)
+
+
+def test_reshape_partitions(tmp_path):
+    engine = Engine()
+    for day, d, qty in [("2024-01-02", 1, "[1, 2]"), ("2024-01-03", 2, "[3, 4]")]:
+        eval_df("t = df[d = %s, sym = [`a`, `b`], qty = %s]" % (d, qty), engine)
+        eval_df(
+            'wpart("%s", %s, `trade`, t, [], false, true)' % (tmp_path, day), engine
+        )
+    eval_df('load("%s")' % tmp_path, engine)
+    assert eval_df(
+        "from trade pivot(`sym`, `d`, `qty`, `sum`) sort {d}", engine
+    ).rows() == [(1, 1, 2), (2, 3, 4)]
+    assert eval_df("pivot(trade, `sym`, `d`, `qty`, `sum`)", engine).columns == [
+        "d",
+        "a",
+        "b",
+    ]
+    assert eval_df("unpivot(trade, `d`, `qty`)", engine).height == 4
+
+
+def test_sql_with():
+    engine = Engine()
+    eval_df("t = df[sym = [`a`, `a`, `b`], qty = [1, 3, 4]]", engine)
+    assert_frame_equal(
+        eval_df(
+            "with {a = from t filter {qty > 1}, b = from a group {sym} select {total = sum qty}} "
+            "from a left join b on {sym} select {sym, qty, total}",
+            engine,
+        ),
+        pl.DataFrame(
+            [
+                pl.Series("sym", ["a", "b"], pl.Categorical),
+                pl.Series("qty", [3, 4]),
+                pl.Series("total", [3, 4]),
+            ]
+        ),
+    )